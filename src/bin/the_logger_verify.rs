@@ -0,0 +1,38 @@
+//! Verifies the hash chain of the log files written with the `audit` feature, reporting every modified, removed or
+//! reordered record.
+//!
+//! ### Usage
+//! ```text
+//! the_logger_verify <log directory> [log file prefix]
+//! ```
+//! The log file prefix defaults to "Log". Exits with 0 if the chain is intact, 1 if it was tampered with, and 2 if the
+//! log files couldn't be read.
+
+use std::process::ExitCode;
+use the_logger::verify_hash_chain;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(log_directory) = args.next() else {
+        eprintln!("usage: the_logger_verify <log directory> [log file prefix]");
+        return ExitCode::from(2);
+    };
+    let log_file_prefix = args.next().unwrap_or_else(|| "Log".to_string());
+
+    match verify_hash_chain(&log_directory, &log_file_prefix) {
+        Ok(violations) if violations.is_empty() => {
+            println!("The hash chain of the log files is intact");
+            ExitCode::SUCCESS
+        },
+        Ok(violations) => {
+            for violation in &violations {
+                println!("{}", violation);
+            }
+            ExitCode::FAILURE
+        },
+        Err(error) => {
+            eprintln!("couldn't read the log files: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
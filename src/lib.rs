@@ -2,5 +2,23 @@
 
 mod logger;
 
-pub use logger::the_logger::TheLogger;
-pub use logger::logger_config::TheLoggerConfig;
\ No newline at end of file
+pub use logger::the_logger::{TheLogger, TheLoggerGuard};
+pub use logger::logger_config::{
+    FlushPolicy, LocationSource, LocationTruncation, LogLevel, MultilineMode, Sampling, TheLoggerConfig
+};
+pub use logger::error::{FailureCallback, FailureStrategy, TheLoggerError};
+pub use logger::fields::FieldValue;
+pub use logger::span::TheLoggerSpan;
+pub use logger::capture::{CapturedRecord, TheLoggerCapture};
+#[doc(hidden)]
+pub use logger::capture::assert_captured;
+#[doc(hidden)]
+pub use logger::rate_limit::{parse_rate_limit_window, CallSite};
+#[cfg(feature = "tracing_layer")]
+pub use logger::tracing_layer::TheLoggerLayer;
+#[cfg(feature = "redaction")]
+pub use logger::redaction::{RedactionCallback, RedactionRule};
+#[cfg(feature = "audit")]
+pub use logger::hash_chain::{verify_hash_chain, ChainViolation, ChainViolationKind};
+#[cfg(feature = "encryption")]
+pub use logger::encryption::{EncryptedLogReader, EncryptionKey};
\ No newline at end of file
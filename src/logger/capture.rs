@@ -0,0 +1,190 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+/// Records collected by a single capture
+type CapturedRecords = Rc<RefCell<Vec<CapturedRecord>>>;
+
+thread_local! {
+    /// Active captures of the current thread, the innermost one last. Records are collected by the innermost capture
+    static CAPTURES: RefCell<Vec<CapturedRecords>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Record collected by a [`TheLoggerCapture`], with its log level and the record as it would've been written into the
+/// log file
+pub struct CapturedRecord {
+    pub level: LogLevel,
+    pub message: String
+}
+
+/// Guard that collects the records logged on the current thread in memory instead of writing them into the log files,
+/// created with [`TheLogger::capture`]. The records are collected until the guard is dropped, and can be checked with
+/// [`assert_logged!`](crate::assert_logged) and [`assert_not_logged!`](crate::assert_not_logged).
+///
+/// Captures are thread-local, so the tests running in parallel don't see each other's records. Records logged from
+/// other threads, such as the workers of a multi-threaded tokio runtime, aren't captured. Captures can be nested, and
+/// the innermost one collects the records.
+pub struct TheLoggerCapture {
+    records: CapturedRecords
+}
+
+impl TheLoggerCapture {
+    /// ## Description
+    /// Returns the records collected so far, in the order they were logged
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.records.borrow().clone()
+    }
+
+    /// ## Description
+    /// Discards the records collected so far
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+    }
+}
+
+impl Drop for TheLoggerCapture {
+    fn drop(&mut self) {
+        //  The thread-local storage could be already destroyed if the guard is dropped while the thread exits
+        let _ = CAPTURES.try_with(|captures| {
+            captures.borrow_mut().retain(|records| !Rc::ptr_eq(records, &self.records));
+        });
+    }
+}
+
+impl TheLogger {
+    /// ## Description
+    /// Starts capturing the records logged on the current thread by any logger instance. Until the returned guard is
+    /// dropped, the records are collected in memory instead of being written into the log files, and no log file or
+    /// directory is created for them. See [`TheLoggerCapture`] for details.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{assert_logged, assert_not_logged, log_warning, TheLogger};
+    ///
+    /// async fn call_service() {
+    ///     log_warning!(TheLogger::instance(), "request timeout after {}ms", 500);
+    /// }
+    ///
+    /// async fn warns_about_the_timeout() {
+    ///     let _capture = TheLogger::capture();
+    ///     call_service().await;
+    ///
+    ///     assert_logged!(level = Warning, contains = "timeout");
+    ///     assert_not_logged!(level = Error);
+    /// }
+    /// ```
+    pub fn capture() -> TheLoggerCapture {
+        let records = CapturedRecords::default();
+        CAPTURES.with(|captures| captures.borrow_mut().push(Rc::clone(&records)));
+        TheLoggerCapture { records }
+    }
+}
+
+#[doc(hidden)]
+/// Collects the record into the innermost capture of the current thread. Returns false if there's no active capture,
+/// so the record must be written into the log file
+pub(super) fn capture_record(level: LogLevel, message: &str) -> bool {
+    CAPTURES
+        .try_with(|captures| {
+            captures.borrow().last().map(|records| {
+                records.borrow_mut().push(CapturedRecord { level, message: message.to_string() });
+            }).is_some()
+        })
+        .unwrap_or(false)
+}
+
+#[doc(hidden)]
+/// Implementation of [`assert_logged!`](crate::assert_logged) and [`assert_not_logged!`](crate::assert_not_logged).
+/// Checks whether the innermost capture of the current thread collected a record with the level and containing the
+/// text, and panics if that isn't the expected outcome
+#[track_caller]
+pub fn assert_captured(level: Option<LogLevel>, contains: Option<&str>, expected: bool) {
+    let Some(records) = CAPTURES.with(|captures| captures.borrow().last().map(|records| records.borrow().clone()))
+    else {
+        panic!("there's no active capture on this thread, start one with TheLogger::capture()");
+    };
+
+    let matches = |record: &CapturedRecord| {
+        level.is_none_or(|level| record.level == level)
+            && contains.is_none_or(|text| record.message.contains(text))
+    };
+    if records.iter().any(matches) == expected {
+        return;
+    }
+
+    let mut expectation = String::from("a record");
+    if let Some(level) = level {
+        expectation.push_str(&format!(" with level {:?}", level));
+    }
+    if let Some(text) = contains {
+        expectation.push_str(&format!(" containing {:?}", text));
+    }
+    let captured: String = records
+        .iter()
+        .map(|record| format!("\n  [{:?}] {}", record.level, record.message))
+        .collect();
+    panic!(
+        "expected {} to {} logged, the captured records are:{}",
+        expectation,
+        if expected { "be" } else { "not be" },
+        if captured.is_empty() { " none".to_string() } else { captured }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assert_logged, assert_not_logged, log_debug, log_info, log_warning};
+    use super::*;
+
+    #[test]
+    fn records_are_captured_by_the_innermost_capture() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let logger = TheLogger::instance();
+            let outer = TheLogger::capture();
+            log_warning!(logger, "request timeout after {}ms", 500);
+            {
+                let inner = TheLogger::capture();
+                log_info!(logger, "retrying");
+                assert_logged!(level = Information, contains = "retrying");
+                assert_not_logged!(contains = "timeout");
+                assert_eq!(inner.records().len(), 1);
+            }
+
+            assert_logged!(level = Warning, contains = "timeout");
+            assert_not_logged!(level = Information);
+            outer.clear();
+            assert_not_logged!(level = Warning);
+        });
+    }
+
+    #[test]
+    fn concurrent_records_keep_their_own_level() {
+        let threads: Vec<_> = [LogLevel::Warning, LogLevel::Debug]
+            .into_iter()
+            .map(|level| std::thread::spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+                runtime.block_on(async {
+                    let capture = TheLogger::capture();
+                    for _ in 0..200 {
+                        match level {
+                            LogLevel::Warning => { log_warning!(TheLogger::instance(), "concurrent record"); },
+                            _ => { log_debug!(TheLogger::instance(), "concurrent record"); }
+                        }
+                    }
+                    assert!(capture.records().iter().all(|record| record.level == level));
+                });
+            }))
+            .collect();
+        threads.into_iter().for_each(|thread| thread.join().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a record with level Error to be logged")]
+    fn missing_records_fail_the_assertion() {
+        let _capture = TheLogger::capture();
+        assert_logged!(level = Error);
+    }
+}
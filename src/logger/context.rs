@@ -0,0 +1,98 @@
+use std::future::Future;
+use std::sync::Arc;
+use crate::logger::fields::FieldValue;
+use crate::logger::the_logger::TheLogger;
+
+/// Context fields of the current scope, including the ones inherited from the enclosing scopes
+type ContextFields = Arc<Vec<(String, FieldValue)>>;
+
+tokio::task_local! {
+    /// Context fields attached to every record logged within a [`TheLogger::scope`], across await points
+    static LOG_CONTEXT: ContextFields;
+}
+
+#[doc(hidden)]
+/// Returns the context fields of the current scope, if the caller is running within one
+pub(super) fn current_context() -> Option<ContextFields> {
+    LOG_CONTEXT.try_with(Arc::clone).ok()
+}
+
+impl TheLogger {
+    /// ## Description
+    /// Runs the future with the provided context fields attached to every record logged within it, by any logger
+    /// instance. The context is task-local, so it's kept across await points of the same tokio task, but it's not
+    /// inherited by spawned tasks.
+    ///
+    /// Scopes can be nested: the inner scope inherits the fields of the enclosing one, and replaces the ones with the
+    /// same key. Context fields are appended to the log content as k=v pairs, before the fields of the log call itself.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, FieldValue, TheLogger};
+    ///
+    /// async fn handle_request(request_id: String, tenant: &str) {
+    ///     let context = vec![
+    ///         ("request_id", FieldValue::from(request_id)),
+    ///         ("tenant", FieldValue::from(tenant))
+    ///     ];
+    ///
+    ///     TheLogger::scope(context, async {
+    ///         //  Logs "loading user request_id=<request_id> tenant=<tenant>"
+    ///         log_info!(TheLogger::instance(), "loading user");
+    ///         load_user().await;
+    ///     }).await;
+    /// }
+    ///
+    /// async fn load_user() {
+    ///     //  The context is also attached to this record
+    ///     log_info!(TheLogger::instance(), "user loaded");
+    /// }
+    /// ```
+    pub async fn scope<F: Future>(fields: Vec<(&str, FieldValue)>, future: F) -> F::Output {
+        let mut context = current_context()
+            .map(|parent| parent.as_ref().clone())
+            .unwrap_or_default();
+        for (key, value) in fields {
+            match context.iter_mut().find(|(existing_key, _)| existing_key == key) {
+                Some((_, existing_value)) => *existing_value = value,
+                None => context.push((key.to_string(), value))
+            }
+        }
+
+        LOG_CONTEXT.scope(Arc::new(context), future).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::log_info;
+    use super::*;
+
+    #[test]
+    fn scopes_are_kept_across_await_points_and_nested() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let capture = TheLogger::capture();
+            let logger = TheLogger::instance();
+            let context = vec![("request_id", FieldValue::from(7)), ("tenant", FieldValue::from("acme"))];
+            TheLogger::scope(context, async {
+                tokio::task::yield_now().await;
+                log_info!(logger, attempt = 1; "outer");
+                TheLogger::scope(vec![("tenant", FieldValue::from("beta")), ("step", FieldValue::from(2))], async {
+                    log_info!(logger, "inner");
+                }).await;
+                log_info!(logger, "outer again");
+                //  Spawned tasks don't inherit the context
+                tokio::spawn(async move { log_info!(logger, "spawned"); }).await.unwrap();
+            }).await;
+            log_info!(logger, "outside");
+
+            let messages: Vec<String> = capture.records().into_iter().map(|record| record.message).collect();
+            assert!(messages[0].ends_with("outer request_id=7 tenant=acme attempt=1"));
+            assert!(messages[1].ends_with("inner request_id=7 tenant=beta step=2"));
+            assert!(messages[2].ends_with("outer again request_id=7 tenant=acme"));
+            assert!(messages[3].ends_with("spawned"));
+            assert!(messages[4].ends_with("outside"));
+        });
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::path::Path;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use crate::logger::error::TheLoggerError;
+
+/// Type of the frame that starts a segment, written every time the log file is opened. Followed by the segment nonce
+const SEGMENT_FRAME: u8 = 0x01;
+
+/// Type of the frame that holds an encrypted record. Followed by the record's counter within the segment, the length
+/// of the ciphertext and the ciphertext itself
+const RECORD_FRAME: u8 = 0x02;
+
+/// Length of the random nonce of each segment. The nonce of each record is the segment nonce followed by its counter
+const SEGMENT_NONCE_LENGTH: usize = 16;
+
+/// Length of the record frame's header: its type, its counter and the length of its ciphertext
+const RECORD_HEADER_LENGTH: usize = 1 + 8 + 4;
+
+/// Length of the authentication tag that ends the ciphertext of every record. Each record is authenticated along with
+/// the tag of the previous record in the file, chaining the records so a removed or reordered one is detected
+const TAG_LENGTH: usize = 16;
+
+#[doc(hidden)]
+/// Authentication tag of a record, which the next record is chained to
+pub(super) type RecordTag = [u8; TAG_LENGTH];
+
+/// Longest ciphertext of a record frame. Longer records aren't written, and longer lengths are rejected when reading
+/// so a corrupted length doesn't make the reader allocate up to 4 GiB
+const MAX_CIPHERTEXT_LENGTH: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, PartialEq, Eq)]
+/// 256-bit symmetric key used to encrypt the log files, and to decrypt them with [`EncryptedLogReader`]. Its Debug
+/// implementation doesn't show the key
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// ## Description
+    /// Creates the key from its 32 bytes. Keep it out of the source code, such as in a secrets manager
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    #[doc(hidden)]
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(key: [u8; 32]) -> Self {
+        Self::new(key)
+    }
+}
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptionKey(***)")
+    }
+}
+
+#[doc(hidden)]
+/// Returns the nonce of a record: the nonce of its segment followed by its counter within the segment
+fn record_nonce(segment_nonce: &[u8; SEGMENT_NONCE_LENGTH], counter: u64) -> XNonce {
+    let mut nonce = XNonce::default();
+    nonce[..SEGMENT_NONCE_LENGTH].copy_from_slice(segment_nonce);
+    nonce[SEGMENT_NONCE_LENGTH..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+#[doc(hidden)]
+/// Returns the authentication tag that ends the record's ciphertext
+fn record_tag(ciphertext: &[u8]) -> RecordTag {
+    let mut tag = [0; TAG_LENGTH];
+    if let Some(ciphertext_tag) = ciphertext.len().checked_sub(TAG_LENGTH).map(|start| &ciphertext[start..]) {
+        tag.copy_from_slice(ciphertext_tag);
+    }
+    tag
+}
+
+#[doc(hidden)]
+/// Segment of the open log file the records are encrypted into. Each segment has its own random nonce, so appending
+/// to an existing file never reuses the nonce of a previous record. Its first record is chained to the last complete
+/// record of the previous segments
+pub(super) struct EncryptedSegment {
+    cipher: XChaCha20Poly1305,
+    nonce: [u8; SEGMENT_NONCE_LENGTH],
+    records: u64,
+    last_tag: RecordTag
+}
+
+impl EncryptedSegment {
+    #[doc(hidden)]
+    /// Starts a new segment at the end of the log file. A frame left incomplete at the end of the file, as by a crash
+    /// while writing it, is cut off first, so the new segment can be read after the records that precede it
+    pub(super) fn start(key: &EncryptionKey, log_file: &Path, file: &mut File) -> std::io::Result<Self> {
+        let (complete_length, last_tag) = complete_frames(log_file)?;
+        if complete_length < file.metadata()?.len() {
+            file.set_len(complete_length)?;
+        }
+
+        let mut nonce = [0; SEGMENT_NONCE_LENGTH];
+        nonce.copy_from_slice(&XChaCha20Poly1305::generate_nonce(&mut OsRng)[..SEGMENT_NONCE_LENGTH]);
+        let mut frame = vec![SEGMENT_FRAME];
+        frame.extend_from_slice(&nonce);
+        file.write_all(&frame)?;
+        Ok(Self { cipher: key.cipher(), nonce, records: 0, last_tag })
+    }
+
+    #[doc(hidden)]
+    /// Encrypts the record into its frame, returned along with its tag. The counter moves forward even if the frame is
+    /// never written, since a nonce must never be used twice, while the chain only moves forward with
+    /// [`EncryptedSegment::advance`], once the frame was written
+    pub(super) fn encrypt(&mut self, record: &str) -> std::io::Result<(Vec<u8>, RecordTag)> {
+        let counter = self.records;
+        self.records += 1;
+        let payload = Payload { msg: record.as_bytes(), aad: &self.last_tag };
+        let ciphertext = self.cipher
+            .encrypt(&record_nonce(&self.nonce, counter), payload)
+            .ok()
+            .filter(|ciphertext| ciphertext.len() <= MAX_CIPHERTEXT_LENGTH)
+            .ok_or_else(|| std::io::Error::other("the record is too long to be encrypted"))?;
+
+        let mut frame = Vec::with_capacity(RECORD_HEADER_LENGTH + ciphertext.len());
+        frame.push(RECORD_FRAME);
+        frame.extend_from_slice(&counter.to_be_bytes());
+        frame.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&ciphertext);
+        Ok((frame, record_tag(&ciphertext)))
+    }
+
+    #[doc(hidden)]
+    pub(super) fn advance(&mut self, tag: RecordTag) {
+        self.last_tag = tag;
+    }
+}
+
+#[doc(hidden)]
+/// Returns the length of the log file up to its last complete frame, along with the tag of its last complete record.
+/// Unknown frames are left untouched, since the file would only be truncated to recover from an incomplete write
+fn complete_frames(log_file: &Path) -> std::io::Result<(u64, RecordTag)> {
+    let file_length = std::fs::metadata(log_file)?.len();
+    let mut reader = BufReader::new(File::open(log_file)?);
+    let mut position = 0;
+    let mut last_tag = [0; TAG_LENGTH];
+    while position < file_length {
+        let mut frame_header = [0; RECORD_HEADER_LENGTH];
+        let available_header = (file_length - position).min(RECORD_HEADER_LENGTH as u64) as usize;
+        reader.read_exact(&mut frame_header[..available_header])?;
+        let frame_length = match frame_header[0] {
+            SEGMENT_FRAME => 1 + SEGMENT_NONCE_LENGTH as u64,
+            RECORD_FRAME if available_header == RECORD_HEADER_LENGTH => {
+                let ciphertext_length = u32::from_be_bytes([
+                    frame_header[9], frame_header[10], frame_header[11], frame_header[12]
+                ]);
+                RECORD_HEADER_LENGTH as u64 + ciphertext_length as u64
+            },
+            RECORD_FRAME => return Ok((position, last_tag)),
+            _ => return Ok((file_length, last_tag))
+        };
+        if position + frame_length > file_length {
+            return Ok((position, last_tag));
+        }
+        let frame_rest = frame_length - available_header as u64;
+        if frame_header[0] == RECORD_FRAME && frame_rest >= TAG_LENGTH as u64 {
+            reader.seek_relative((frame_rest - TAG_LENGTH as u64) as i64)?;
+            reader.read_exact(&mut last_tag)?;
+        } else {
+            reader.seek_relative(frame_rest as i64)?;
+        }
+        position += frame_length;
+    }
+    Ok((position, last_tag))
+}
+
+/// Reader of the log files written with encryption enabled, see
+/// [`TheLoggerConfig::enable_encryption`](crate::TheLoggerConfig::enable_encryption). It iterates over the decrypted
+/// records, and stops at the last complete record when the file ends with an incomplete one, as after a crash. A
+/// record that can't be decrypted, because the key is wrong or the file was tampered with, is returned as an error
+/// and ends the iteration. Since every record is chained to the previous one, removing or reordering records or whole
+/// segments makes the next record fail as well. Removing the last records of the file isn't detected, since no later
+/// record is chained to them.
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{EncryptedLogReader, EncryptionKey, TheLoggerError};
+///
+/// fn print_logs(key: &EncryptionKey) -> Result<(), TheLoggerError> {
+///     for record in EncryptedLogReader::open("./logs/Log 2024-01-01.log.enc", key)? {
+///         println!("{}", record?);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct EncryptedLogReader<R> {
+    reader: R,
+    cipher: XChaCha20Poly1305,
+    segment: Option<([u8; SEGMENT_NONCE_LENGTH], Option<u64>)>,
+    last_tag: RecordTag,
+    finished: bool
+}
+
+impl EncryptedLogReader<BufReader<File>> {
+    /// ## Description
+    /// Opens the encrypted log file to read its records with the given key
+    pub fn open(path: impl AsRef<Path>, key: &EncryptionKey) -> Result<Self, TheLoggerError> {
+        let file = File::open(path).map_err(TheLoggerError::Read)?;
+        Ok(Self::new(BufReader::new(file), key))
+    }
+}
+
+impl<R: Read> EncryptedLogReader<R> {
+    /// ## Description
+    /// Creates a reader of the encrypted records read from the given reader, with the given key
+    pub fn new(reader: R, key: &EncryptionKey) -> Self {
+        Self { reader, cipher: key.cipher(), segment: None, last_tag: [0; TAG_LENGTH], finished: false }
+    }
+
+    #[doc(hidden)]
+    /// Fills the buffer, returning false if the reader ends before it's full
+    fn read_frame_part(&mut self, buffer: &mut [u8]) -> Result<bool, TheLoggerError> {
+        match self.reader.read_exact(buffer) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(error) => Err(TheLoggerError::Read(error))
+        }
+    }
+
+    #[doc(hidden)]
+    /// Reads frames until the next record, returning None when the reader ends or its last frame is incomplete
+    fn next_record(&mut self) -> Result<Option<String>, TheLoggerError> {
+        loop {
+            let mut frame_type = [0; 1];
+            if !self.read_frame_part(&mut frame_type)? {
+                return Ok(None);
+            }
+
+            match frame_type[0] {
+                SEGMENT_FRAME => {
+                    let mut nonce = [0; SEGMENT_NONCE_LENGTH];
+                    if !self.read_frame_part(&mut nonce)? {
+                        return Ok(None);
+                    }
+                    self.segment = Some((nonce, None));
+                },
+                RECORD_FRAME => {
+                    let mut frame_header = [0; RECORD_HEADER_LENGTH - 1];
+                    if !self.read_frame_part(&mut frame_header)? {
+                        return Ok(None);
+                    }
+                    let counter = u64::from_be_bytes(frame_header[..8].try_into().unwrap_or_default());
+                    let ciphertext_length = u32::from_be_bytes(frame_header[8..].try_into().unwrap_or_default());
+                    if ciphertext_length as usize > MAX_CIPHERTEXT_LENGTH {
+                        return Err(TheLoggerError::Decrypt);
+                    }
+                    let mut ciphertext = vec![0; ciphertext_length as usize];
+                    if !self.read_frame_part(&mut ciphertext)? {
+                        return Ok(None);
+                    }
+
+                    //  Counters only move forward within a segment, a repeated or older one means reordered records.
+                    //  They may skip a record that was encrypted but never written, a removed one fails the chain
+                    let Some((nonce, last_counter)) = self.segment.as_mut() else {
+                        return Err(TheLoggerError::Decrypt);
+                    };
+                    if last_counter.is_some_and(|last_counter| counter <= last_counter) {
+                        return Err(TheLoggerError::Decrypt);
+                    }
+                    *last_counter = Some(counter);
+                    let payload = Payload { msg: ciphertext.as_slice(), aad: &self.last_tag };
+                    let record = self.cipher
+                        .decrypt(&record_nonce(nonce, counter), payload)
+                        .map_err(|_| TheLoggerError::Decrypt)?;
+                    self.last_tag = record_tag(&ciphertext);
+                    return String::from_utf8(record).map(Some).map_err(|_| TheLoggerError::Decrypt);
+                },
+                _ => return Err(TheLoggerError::Decrypt)
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for EncryptedLogReader<R> {
+    type Item = Result<String, TheLoggerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let record = self.next_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.finished = true;
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the record's frame into the file, returning where it starts and ends
+    fn write_record(file: &mut File, segment: &mut EncryptedSegment, record: &str) -> std::ops::Range<usize> {
+        let start = file.metadata().unwrap().len() as usize;
+        let (frame, tag) = segment.encrypt(record).unwrap();
+        file.write_all(&frame).unwrap();
+        segment.advance(tag);
+        start..start + frame.len()
+    }
+
+    #[test]
+    fn records_are_read_up_to_the_last_complete_one() {
+        let log_file = std::env::temp_dir().join(format!("the_logger_encryption_{}.log.enc", std::process::id()));
+        let key = EncryptionKey::new([7; 32]);
+        let _ = std::fs::remove_file(&log_file);
+        let mut file = File::options().create(true).append(true).open(&log_file).unwrap();
+        let mut segment = EncryptedSegment::start(&key, &log_file, &mut file).unwrap();
+        for record in ["first record", "second\nrecord"] {
+            write_record(&mut file, &mut segment, record);
+        }
+        //  An incomplete frame is cut off when a new segment is started, and the records after it are still readable
+        file.write_all(&segment.encrypt("cut off by a crash").unwrap().0[..20]).unwrap();
+        let mut segment = EncryptedSegment::start(&key, &log_file, &mut file).unwrap();
+        write_record(&mut file, &mut segment, "after the restart");
+        file.write_all(&segment.encrypt("incomplete").unwrap().0[..30]).unwrap();
+
+        let records: Result<Vec<String>, TheLoggerError> = EncryptedLogReader::open(&log_file, &key).unwrap().collect();
+        assert_eq!(records.unwrap(), ["first record", "second\nrecord", "after the restart"]);
+
+        let mut wrong_key_reader = EncryptedLogReader::open(&log_file, &EncryptionKey::new([8; 32])).unwrap();
+        assert!(matches!(wrong_key_reader.next(), Some(Err(TheLoggerError::Decrypt))));
+        assert!(wrong_key_reader.next().is_none());
+        std::fs::remove_file(&log_file).unwrap();
+    }
+
+    #[test]
+    fn removed_records_and_segments_are_detected() {
+        let log_file = std::env::temp_dir().join(format!("the_logger_encryption_chain_{}.log.enc", std::process::id()));
+        let key = EncryptionKey::new([7; 32]);
+        let _ = std::fs::remove_file(&log_file);
+        let mut file = File::options().create(true).append(true).open(&log_file).unwrap();
+        let mut segment = EncryptedSegment::start(&key, &log_file, &mut file).unwrap();
+        write_record(&mut file, &mut segment, "user created");
+        let removed_record = write_record(&mut file, &mut segment, "role granted");
+        //  A record that's encrypted but never written skips its counter without breaking the chain
+        segment.encrypt("never written").unwrap();
+        write_record(&mut file, &mut segment, "user deleted");
+        let second_segment_start = file.metadata().unwrap().len() as usize;
+        let mut segment = EncryptedSegment::start(&key, &log_file, &mut file).unwrap();
+        write_record(&mut file, &mut segment, "login");
+        let content = std::fs::read(&log_file).unwrap();
+        std::fs::remove_file(&log_file).unwrap();
+
+        let records = |content: &[u8]| -> Vec<Result<String, TheLoggerError>> {
+            EncryptedLogReader::new(content, &key).collect()
+        };
+        let all_records: Result<Vec<String>, TheLoggerError> = records(&content).into_iter().collect();
+        assert_eq!(all_records.unwrap(), ["user created", "role granted", "user deleted", "login"]);
+
+        let without_record = [&content[..removed_record.start], &content[removed_record.end..]].concat();
+        let read = records(&without_record);
+        assert!(matches!(read.as_slice(), [Ok(_), Err(TheLoggerError::Decrypt)]));
+
+        let without_first_segment = &content[second_segment_start..];
+        assert!(matches!(records(without_first_segment).as_slice(), [Err(TheLoggerError::Decrypt)]));
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let key = EncryptionKey::new([7; 32]);
+        let mut frames = vec![SEGMENT_FRAME];
+        frames.extend_from_slice(&[0; SEGMENT_NONCE_LENGTH]);
+        frames.push(RECORD_FRAME);
+        frames.extend_from_slice(&0u64.to_be_bytes());
+        frames.extend_from_slice(&u32::MAX.to_be_bytes());
+
+        let mut reader = EncryptedLogReader::new(frames.as_slice(), &key);
+        assert!(matches!(reader.next(), Some(Err(TheLoggerError::Decrypt))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+
+#[derive(Debug)]
+/// Errors that TheLogger can run into while setting up the log file or writing into it
+pub enum TheLoggerError {
+    /// The logger was initialized more than once, or after its instance was already in use
+    AlreadyInitialized,
+    /// A logger instance is already registered with the given name
+    NameAlreadyRegistered(String),
+    /// A global logger was already installed in the logging facade
+    GlobalLoggerAlreadySet,
+    /// The directory that holds the log files couldn't be created
+    CreateDirectory(std::io::Error),
+    /// The log file couldn't be opened or created
+    OpenFile(std::io::Error),
+    /// A record couldn't be written into the log file
+    Write(std::io::Error),
+    /// The buffered records couldn't be flushed into the log file
+    Flush(std::io::Error),
+    /// The log file couldn't be synced to disk
+    Sync(std::io::Error),
+    /// A log file couldn't be read
+    Read(std::io::Error),
+    /// A record of an encrypted log file couldn't be decrypted, because the key is wrong or the file was tampered with
+    Decrypt,
+    /// The logger was flushed or shut down from a callback running while it was writing a record, such as a failure
+    /// callback or a redaction callback
+    Reentrant
+}
+
+impl Display for TheLoggerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TheLoggerError::AlreadyInitialized => write!(f, "the logger is already initialized"),
+            TheLoggerError::NameAlreadyRegistered(name) => write!(f, "a logger named '{}' is already registered", name),
+            TheLoggerError::GlobalLoggerAlreadySet => write!(f, "a global logger is already set"),
+            TheLoggerError::CreateDirectory(error) => write!(f, "couldn't create the logs directory: {}", error),
+            TheLoggerError::OpenFile(error) => write!(f, "couldn't open the log file: {}", error),
+            TheLoggerError::Write(error) => write!(f, "couldn't write into the log file: {}", error),
+            TheLoggerError::Flush(error) => write!(f, "couldn't flush the log file: {}", error),
+            TheLoggerError::Sync(error) => write!(f, "couldn't sync the log file to disk: {}", error),
+            TheLoggerError::Read(error) => write!(f, "couldn't read the log file: {}", error),
+            TheLoggerError::Decrypt => {
+                write!(f, "couldn't decrypt the record, the key is wrong or the file was tampered with")
+            },
+            TheLoggerError::Reentrant => {
+                write!(f, "the logger can't be flushed or shut down from a callback running while it writes a record")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TheLoggerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TheLoggerError::AlreadyInitialized
+            | TheLoggerError::NameAlreadyRegistered(_)
+            | TheLoggerError::GlobalLoggerAlreadySet
+            | TheLoggerError::Decrypt
+            | TheLoggerError::Reentrant => None,
+            TheLoggerError::CreateDirectory(error)
+            | TheLoggerError::OpenFile(error)
+            | TheLoggerError::Write(error)
+            | TheLoggerError::Flush(error)
+            | TheLoggerError::Sync(error)
+            | TheLoggerError::Read(error) => Some(error)
+        }
+    }
+}
+
+/// Signature of the user callback invoked by [`FailureStrategy::Callback`]. It receives the error and, when the
+/// failure happened while logging, the record that couldn't be written
+pub type FailureCallback = Arc<dyn Fn(&TheLoggerError, Option<&str>) + Send + Sync>;
+
+#[derive(Clone, Default)]
+/// Strategies available to handle a failure while logging, since logging calls don't return errors to the caller
+pub enum FailureStrategy {
+    /// Silently discards the record that couldn't be written
+    Ignore,
+    /// Writes the record that couldn't be written into stderr. The error itself is reported only once until the
+    /// logger recovers from it. This is the default
+    #[default]
+    Stderr,
+    /// Invokes the user provided callback with the error and the record that couldn't be written. It runs while the
+    /// logger is writing the record, so the records it logs with the same logger are discarded, its configuration
+    /// changes are skipped, and flushing or shutting the logger down returns [`TheLoggerError::Reentrant`]
+    Callback(FailureCallback)
+}
+
+impl Debug for FailureStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureStrategy::Ignore => write!(f, "Ignore"),
+            FailureStrategy::Stderr => write!(f, "Stderr"),
+            FailureStrategy::Callback(_) => write!(f, "Callback")
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+impl TheLogger {
+    /// ## Description
+    /// Logs the error and its full chain of sources in a single record, each source in its own "caused by:" line, as
+    /// in:
+    /// ```text
+    /// while loading user 42: couldn't read the config
+    /// caused by: couldn't open config.toml
+    /// caused by: No such file or directory (os error 2)
+    /// ```
+    /// The line breaks are written according to the configured multi-line mode, see
+    /// [`TheLoggerConfig::multiline_mode`](crate::TheLoggerConfig::multiline_mode). If backtraces are enabled with
+    /// the RUST_BACKTRACE or RUST_LIB_BACKTRACE variables, the backtrace of the call is appended too, since the
+    /// error's own backtrace isn't accessible on stable Rust.
+    ///
+    /// The record is logged with the ERROR level. The [`log_error_chain!`](crate::log_error_chain) macro fills in the
+    /// call site location.
+    pub async fn log_error_chain_in_file(
+        &self,
+        module_path: &str,
+        location: (&str, u32, u32),
+        context_msg: Option<&str>,
+        error: &dyn Error
+    ) {
+        let mut msg = String::new();
+        if let Some(context_msg) = context_msg {
+            msg.push_str(format!("{}: ", context_msg).as_str());
+        }
+        msg.push_str(error.to_string().as_str());
+
+        let mut source = error.source();
+        while let Some(cause) = source {
+            msg.push_str(format!("\ncaused by: {}", cause).as_str());
+            source = cause.source();
+        }
+
+        let backtrace = Backtrace::capture();
+        if backtrace.status() == BacktraceStatus::Captured {
+            msg.push_str(format!("\nstack backtrace:\n{}", backtrace).as_str());
+        }
+
+        self.log_in_file_with_fields(LogLevel::Error, module_path, location, &[], &msg).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Display, Formatter};
+    use crate::log_error_chain;
+    use super::*;
+
+    #[derive(Debug)]
+    /// Error with a message and an optional source, to build chains of any depth
+    struct ChainedError {
+        message: &'static str,
+        source: Option<Box<dyn Error>>
+    }
+
+    impl Display for ChainedError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.message)
+        }
+    }
+
+    impl Error for ChainedError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            self.source.as_deref()
+        }
+    }
+
+    #[test]
+    fn nested_sources_are_logged_in_a_single_record() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "couldn't open config.toml");
+        let config_error = ChainedError { message: "couldn't read the config", source: Some(Box::new(io_error)) };
+        let error = ChainedError { message: "couldn't load the user", source: Some(Box::new(config_error)) };
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let capture = TheLogger::capture();
+            log_error_chain!(TheLogger::instance(), &error, "while loading user {}", 42);
+            log_error_chain!(TheLogger::instance(), &ChainedError { message: "no sources", source: None });
+
+            let records = capture.records();
+            assert!(records.iter().all(|record| record.level == LogLevel::Error));
+            assert!(records[0].message.contains(
+                "while loading user 42: couldn't load the user\ncaused by: couldn't read the config\n\
+                caused by: couldn't open config.toml"
+            ));
+            //  The backtrace follows the chain only if it's enabled in the environment
+            let after_chain = records[1].message.split_once("no sources").unwrap().1;
+            assert!(after_chain.is_empty() || after_chain.starts_with("\nstack backtrace:\n"));
+        });
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt::{Debug, Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Typed value of a structured key/value field attached to a record. Fields are captured by the log macros with the
+/// `key = value` syntax, where the value is converted with its `From` implementation, or formatted with `%value` to use
+/// its Display implementation or `?value` to use its Debug implementation.
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{log_info, TheLogger};
+///
+/// async fn request_served(path: &str, status: u16, elapsed_ms: u128) {
+///     let logger = TheLogger::instance();
+///     log_info!(logger, path = path, status = status; "request served in {}ms", elapsed_ms);
+/// }
+/// ```
+pub enum FieldValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String)
+}
+
+impl FieldValue {
+    /// ## Description
+    /// Captures the value using its Display implementation. Used by the log macros for `%value` fields
+    pub fn display(value: &dyn Display) -> Self {
+        FieldValue::Str(value.to_string())
+    }
+
+    /// ## Description
+    /// Captures the value using its Debug implementation. Used by the log macros for `?value` fields
+    pub fn debug(value: &dyn Debug) -> Self {
+        FieldValue::Str(format!("{:?}", value))
+    }
+}
+
+impl Display for FieldValue {
+    /// Text values containing whitespace, quotes or '=' are quoted to keep the k=v pairs parseable
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Bool(value) => write!(f, "{}", value),
+            FieldValue::I64(value) => write!(f, "{}", value),
+            FieldValue::U64(value) => write!(f, "{}", value),
+            FieldValue::F64(value) => write!(f, "{}", value),
+            FieldValue::Str(value) => {
+                if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+                    write!(f, "{:?}", value)
+                } else {
+                    write!(f, "{}", value)
+                }
+            }
+        }
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self {
+        FieldValue::F64(value as f64)
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::F64(value)
+    }
+}
+
+impl From<char> for FieldValue {
+    fn from(value: char) -> Self {
+        FieldValue::Str(value.to_string())
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::Str(value)
+    }
+}
+
+impl From<&String> for FieldValue {
+    fn from(value: &String) -> Self {
+        FieldValue::Str(value.clone())
+    }
+}
+
+macro_rules! impl_from_signed {
+    ($($signed:ty),*) => {
+        $(
+            impl From<$signed> for FieldValue {
+                fn from(value: $signed) -> Self {
+                    FieldValue::I64(value as i64)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_unsigned {
+    ($($unsigned:ty),*) => {
+        $(
+            impl From<$unsigned> for FieldValue {
+                fn from(value: $unsigned) -> Self {
+                    FieldValue::U64(value as u64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_signed!(i8, i16, i32, i64, isize);
+impl_from_unsigned!(u8, u16, u32, u64, usize);
+
+#[cfg(test)]
+mod tests {
+    use crate::{log_info, TheLogger};
+    use super::*;
+
+    #[test]
+    fn values_are_rendered_and_quoted_when_needed() {
+        assert_eq!(FieldValue::from(true).to_string(), "true");
+        assert_eq!(FieldValue::from(-7i8).to_string(), "-7");
+        assert_eq!(FieldValue::from(42usize).to_string(), "42");
+        assert_eq!(FieldValue::from(1.5f32).to_string(), "1.5");
+        assert_eq!(FieldValue::from('x').to_string(), "x");
+        assert_eq!(FieldValue::from("/users").to_string(), "/users");
+        assert_eq!(FieldValue::from("").to_string(), "\"\"");
+        assert_eq!(FieldValue::from("not found").to_string(), "\"not found\"");
+        assert_eq!(FieldValue::from("a=b").to_string(), "\"a=b\"");
+        assert_eq!(FieldValue::from("say \"hi\"").to_string(), "\"say \\\"hi\\\"\"");
+        assert_eq!(FieldValue::display(&std::net::Ipv4Addr::LOCALHOST).to_string(), "127.0.0.1");
+        assert_eq!(FieldValue::debug(&Some(3)).to_string(), "Some(3)");
+        assert_eq!(FieldValue::debug(&"quoted").to_string(), "\"\\\"quoted\\\"\"");
+    }
+
+    #[test]
+    fn fields_follow_the_message() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let capture = TheLogger::capture();
+            let address = std::net::Ipv4Addr::LOCALHOST;
+            log_info!(TheLogger::instance(), path = "/a b", status = 200u16, peer = %address, retry = ?None::<u8>;
+                "request served in {}ms", 5);
+
+            let expected = "request served in 5ms path=\"/a b\" status=200 peer=127.0.0.1 retry=None";
+            assert!(capture.records()[0].message.ends_with(expected));
+        });
+    }
+}
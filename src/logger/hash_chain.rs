@@ -0,0 +1,389 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+
+/// Prefix of the header line that starts the hash chain of every log file, followed by the seed of the chain
+const HEADER_PREFIX: &str = "# the_logger hash chain seed=";
+
+/// Separator between a record and its chained hash
+const HASH_SEPARATOR: &str = " #hash=";
+
+/// Seed of the first chain in a logs directory, when there's no previous file to take it from
+const ZERO_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Length of the end of a log file first read to find the hash its chain ends with. It's doubled until a chained line
+/// is found, so a long tail written without the chain is still skipped
+const TAIL_LENGTH: u64 = 64 * 1024;
+
+#[doc(hidden)]
+/// State of the hash chain of the open log file: the hash of the last record written into it
+pub(super) struct HashChain {
+    last_hash: String
+}
+
+impl HashChain {
+    #[doc(hidden)]
+    /// Resumes the chain of the log file from its last hash. If the file has no chained record yet, a header line is
+    /// written seeding the chain with the final hash of the previous log file in the directory. A line left incomplete
+    /// at the end of the file, as by a crash while writing it, is cut off first, so the next record starts its own line
+    pub(super) fn resume(log_file: &Path, file: &mut File) -> std::io::Result<Self> {
+        let mut reader = File::open(log_file)?;
+        let complete_length = complete_lines_length(&mut reader)?;
+        if complete_length < file.metadata()?.len() {
+            file.set_len(complete_length)?;
+        }
+        if let Some(last_hash) = last_chain_hash_in_file(&mut reader)? {
+            return Ok(Self { last_hash });
+        }
+
+        let seed = match previous_log_file(log_file)? {
+            Some(previous_log_file) => final_hash(&previous_log_file)?,
+            None => ZERO_HASH.to_string()
+        };
+        writeln!(file, "{}{}", HEADER_PREFIX, seed)?;
+        Ok(Self { last_hash: seed })
+    }
+
+    #[doc(hidden)]
+    /// Returns the record with its chained hash appended, along with the hash itself. The chain only moves forward
+    /// with [`HashChain::advance`], once the record was written
+    pub(super) fn chain(&self, record: &str) -> (String, String) {
+        let hash = chained_hash(&self.last_hash, record);
+        (format!("{}{}{}", record, HASH_SEPARATOR, hash), hash)
+    }
+
+    #[doc(hidden)]
+    pub(super) fn advance(&mut self, hash: String) {
+        self.last_hash = hash;
+    }
+}
+
+#[doc(hidden)]
+/// Hash of a record, chained to the hash of the previous one: SHA-256 of the previous hash followed by the record
+fn chained_hash(previous_hash: &str, record: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(record.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[doc(hidden)]
+/// Splits the line of a chained record into the record and its hash, or returns None if the line isn't chained
+fn split_chained_line(line: &str) -> Option<(&str, &str)> {
+    let (record, hash) = line.rsplit_once(HASH_SEPARATOR)?;
+    let is_hash = hash.len() == ZERO_HASH.len() && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'));
+    is_hash.then_some((record, hash))
+}
+
+#[doc(hidden)]
+/// Returns the hash the chain of the file's content ends with: the hash of its last record, or the seed of its header
+/// if it has no records yet. Returns None if the content isn't chained
+fn last_chain_hash(content: &str) -> Option<&str> {
+    content.lines().rev().find_map(|line| {
+        split_chained_line(line)
+            .map(|(_, hash)| hash)
+            .or_else(|| line.strip_prefix(HEADER_PREFIX))
+    })
+}
+
+#[doc(hidden)]
+/// Returns the hash the chain of the file ends with, reading only its end. Bytes that aren't valid UTF-8, such as a
+/// record torn in the middle of a character, are replaced, so the line holding them just isn't chained
+fn last_chain_hash_in_file(file: &mut File) -> std::io::Result<Option<String>> {
+    let length = file.metadata()?.len();
+    let mut tail_length = TAIL_LENGTH.min(length);
+    loop {
+        file.seek(SeekFrom::Start(length - tail_length))?;
+        let mut tail = vec![0; tail_length as usize];
+        file.read_exact(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        //  Unless the whole file was read, the first line of the tail may start in the middle of a line
+        let whole_file = tail_length == length;
+        let lines = if whole_file { &tail } else { tail.split_once('\n').map_or("", |(_, lines)| lines) };
+        if let Some(last_hash) = last_chain_hash(lines) {
+            return Ok(Some(last_hash.to_string()));
+        }
+        if whole_file {
+            return Ok(None);
+        }
+        tail_length = (tail_length * 2).min(length);
+    }
+}
+
+#[doc(hidden)]
+/// Returns the length of the file up to the line break that ends its last complete line
+fn complete_lines_length(file: &mut File) -> std::io::Result<u64> {
+    let mut end = file.metadata()?.len();
+    let mut chunk = vec![0; TAIL_LENGTH as usize];
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_LENGTH);
+        let chunk = &mut chunk[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(line_break) = chunk.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(start + line_break as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+#[doc(hidden)]
+/// Returns the hash the chain of the log file ends with, or the zero hash if it isn't chained
+fn final_hash(log_file: &Path) -> std::io::Result<String> {
+    let last_hash = last_chain_hash_in_file(&mut File::open(log_file)?)?;
+    Ok(last_hash.unwrap_or_else(|| ZERO_HASH.to_string()))
+}
+
+#[doc(hidden)]
+/// Returns the log files of the directory with the given prefix, named as "<prefix> <date>.log", sorted by date
+fn log_files(log_directory: &Path, log_file_prefix: &str) -> std::io::Result<Vec<PathBuf>> {
+    let mut log_files = Vec::new();
+    for entry in fs::read_dir(log_directory)? {
+        let path = entry?.path();
+        let is_log_file = path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(log_file_prefix))
+            .and_then(|name| name.strip_prefix(' '))
+            .and_then(|name| name.strip_suffix(".log"))
+            .is_some_and(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+        if is_log_file {
+            log_files.push(path);
+        }
+    }
+    //  The dates are formatted from the year down to the day, so sorting by name sorts them by date
+    log_files.sort();
+    Ok(log_files)
+}
+
+#[doc(hidden)]
+/// Returns the log file with the same prefix that comes right before the given one, if any
+fn previous_log_file(log_file: &Path) -> std::io::Result<Option<PathBuf>> {
+    let (Some(log_directory), Some(name)) = (log_file.parent(), log_file.file_name().and_then(|name| name.to_str()))
+    else {
+        return Ok(None);
+    };
+    let log_file_prefix = name.rsplit_once(' ').map_or(name, |(prefix, _)| prefix);
+    Ok(log_files(log_directory, log_file_prefix)?
+        .into_iter()
+        .rfind(|path| path.as_path() < log_file))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Kinds of violations of the hash chain found by [`verify_hash_chain`]
+pub enum ChainViolationKind {
+    /// The record's hash doesn't match its content and the previous hash: the record was modified, or the records
+    /// around it were removed or reordered
+    HashMismatch,
+    /// The line isn't part of the chain: it was inserted, or written while the hash chain was disabled
+    Unchained,
+    /// The file doesn't start with the header that seeds its chain
+    MissingHeader,
+    /// The seed in the file's header doesn't match the final hash of the previous file: records at the end of the
+    /// previous file, or whole files in between, were removed
+    SeedMismatch,
+    /// The file couldn't be read, so its records couldn't be verified
+    Unreadable
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Violation of the hash chain found by [`verify_hash_chain`], with the file and line where it was detected
+pub struct ChainViolation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub kind: ChainViolationKind
+}
+
+impl Display for ChainViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let description = match self.kind {
+            ChainViolationKind::HashMismatch => "the hash doesn't match, the record was modified, removed or reordered",
+            ChainViolationKind::Unchained => "the line isn't part of the hash chain",
+            ChainViolationKind::MissingHeader => "the file doesn't start with the hash chain header",
+            ChainViolationKind::SeedMismatch => "the seed doesn't match the final hash of the previous file",
+            ChainViolationKind::Unreadable => "the file couldn't be read"
+        };
+        write!(f, "{}:{}: {}", self.file.display(), self.line, description)
+    }
+}
+
+/// ## Description
+/// Verifies the hash chain of every log file with the given prefix in the logs directory, written with the hash chain
+/// enabled, see [`TheLoggerConfig::enable_hash_chain`](crate::TheLoggerConfig::enable_hash_chain). Returns the
+/// violations found, which is empty if no record was modified, removed or reordered. The seed of the oldest file isn't
+/// checked, so deleting old files is allowed. A file that can't be read is reported as a violation on its line 0, and
+/// the seed of the next file isn't checked either.
+///
+/// Records still buffered by the logger aren't in the files yet, so flush the logger before verifying a directory
+/// it's writing into. The `the_logger_verify` binary runs this verification from the command line.
+///
+/// ### Example
+/// ```rust
+/// use the_logger::verify_hash_chain;
+///
+/// fn audit() -> std::io::Result<bool> {
+///     let violations = verify_hash_chain("./audit_logs/", "Audit")?;
+///     for violation in &violations {
+///         eprintln!("{}", violation);
+///     }
+///     Ok(violations.is_empty())
+/// }
+/// ```
+pub fn verify_hash_chain(
+    log_directory: impl AsRef<Path>,
+    log_file_prefix: &str
+) -> std::io::Result<Vec<ChainViolation>> {
+    let mut violations = Vec::new();
+    let mut previous_final_hash: Option<String> = None;
+    for log_file in log_files(log_directory.as_ref(), log_file_prefix)? {
+        let mut violation = |line: usize, kind: ChainViolationKind| {
+            violations.push(ChainViolation { file: log_file.clone(), line, kind });
+        };
+        let Ok(content) = fs::read(&log_file) else {
+            violation(0, ChainViolationKind::Unreadable);
+            previous_final_hash = None;
+            continue;
+        };
+        //  Invalid UTF-8 is replaced, so the lines holding it are reported instead of failing the whole verification
+        let content = String::from_utf8_lossy(&content);
+
+        if !content.starts_with(HEADER_PREFIX) {
+            violation(1, ChainViolationKind::MissingHeader);
+        }
+
+        //  Multi-line records are written as they are, only their last line carries the hash. The lines are split only
+        //  on '\n', since a '\r' before it is part of the hashed record
+        let mut last_hash = previous_final_hash.clone().unwrap_or_else(|| ZERO_HASH.to_string());
+        let mut record_lines: Vec<&str> = Vec::new();
+        let mut record_start = 0;
+        let lines = content.strip_suffix('\n').unwrap_or(&content).split('\n');
+        for (number, line) in lines.enumerate().map(|(index, line)| (index + 1, line)) {
+            if let Some(seed) = line.strip_prefix(HEADER_PREFIX) {
+                //  A header in the middle of the file restarts the chain, after lines written without it
+                if !record_lines.is_empty() {
+                    violation(record_start, ChainViolationKind::Unchained);
+                    record_lines.clear();
+                }
+                if number == 1 && previous_final_hash.as_deref().is_some_and(|final_hash| final_hash != seed) {
+                    violation(number, ChainViolationKind::SeedMismatch);
+                }
+                last_hash = seed.to_string();
+                continue;
+            }
+
+            if record_lines.is_empty() {
+                record_start = number;
+            }
+            match split_chained_line(line) {
+                Some((last_record_line, hash)) => {
+                    record_lines.push(last_record_line);
+                    if chained_hash(&last_hash, &record_lines.join("\n")) != hash {
+                        violation(record_start, ChainViolationKind::HashMismatch);
+                    }
+                    //  Continue from the hash in the file, so a single modified record is reported only once
+                    last_hash = hash.to_string();
+                    record_lines.clear();
+                },
+                None => record_lines.push(line)
+            }
+        }
+        if !record_lines.is_empty() {
+            violation(record_start, ChainViolationKind::Unchained);
+        }
+        previous_final_hash = Some(last_hash);
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chained_file(seed: &str, records: &[&str]) -> (String, String) {
+        let mut chain = HashChain { last_hash: seed.to_string() };
+        let mut content = format!("{}{}\n", HEADER_PREFIX, seed);
+        for record in records {
+            let (line, hash) = chain.chain(record);
+            content.push_str(&line);
+            content.push('\n');
+            chain.advance(hash);
+        }
+        (content, chain.last_hash)
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_chain_{}", std::process::id()));
+        fs::create_dir_all(&log_directory).unwrap();
+        let (first, final_hash) = chained_file(ZERO_HASH, &["user created", "role granted\nto admin", "user deleted"]);
+        let (second, _) = chained_file(&final_hash, &["login", "logout"]);
+        fs::write(log_directory.join("Audit 2024-01-01.log"), &first).unwrap();
+        fs::write(log_directory.join("Audit 2024-01-02.log"), &second).unwrap();
+        assert_eq!(verify_hash_chain(&log_directory, "Audit").unwrap(), []);
+
+        let kinds = |log_directory: &Path| -> Vec<(usize, ChainViolationKind)> {
+            verify_hash_chain(log_directory, "Audit").unwrap().into_iter().map(|v| (v.line, v.kind)).collect()
+        };
+        fs::write(log_directory.join("Audit 2024-01-01.log"), first.replace("granted", "revoked")).unwrap();
+        assert_eq!(kinds(&log_directory), [(3, ChainViolationKind::HashMismatch)]);
+
+        let removed: Vec<&str> = first.lines().filter(|line| !line.starts_with("user created")).collect();
+        fs::write(log_directory.join("Audit 2024-01-01.log"), removed.join("\n")).unwrap();
+        assert_eq!(kinds(&log_directory), [(2, ChainViolationKind::HashMismatch)]);
+
+        let truncated: Vec<&str> = first.lines().filter(|line| !line.starts_with("user deleted")).collect();
+        fs::write(log_directory.join("Audit 2024-01-01.log"), truncated.join("\n")).unwrap();
+        assert_eq!(kinds(&log_directory), [(1, ChainViolationKind::SeedMismatch)]);
+
+        fs::remove_dir_all(&log_directory).unwrap();
+    }
+
+    #[test]
+    fn torn_last_lines_are_cut_off_when_resuming() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_chain_torn_{}", std::process::id()));
+        fs::create_dir_all(&log_directory).unwrap();
+        let log_file = log_directory.join("Audit 2024-01-01.log");
+        //  The last record is longer than the first tail read, and the torn one ends in the middle of a character
+        let long_record = "x".repeat(TAIL_LENGTH as usize * 2);
+        let (content, final_hash) = chained_file(ZERO_HASH, &["user created", &long_record]);
+        let mut torn_record = "role granted to ü".as_bytes().to_vec();
+        torn_record.pop();
+        fs::write(&log_file, [content.as_bytes(), &torn_record].concat()).unwrap();
+
+        let mut file = fs::OpenOptions::new().append(true).open(&log_file).unwrap();
+        let mut chain = HashChain::resume(&log_file, &mut file).unwrap();
+        assert_eq!(chain.last_hash, final_hash);
+        let (line, hash) = chain.chain("user deleted");
+        writeln!(file, "{}", line).unwrap();
+        chain.advance(hash);
+
+        let violations = verify_hash_chain(&log_directory, "Audit").unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(violations, []);
+    }
+
+    #[test]
+    fn unreadable_files_and_invalid_lines_are_reported() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_chain_unreadable_{}", std::process::id()));
+        fs::create_dir_all(log_directory.join("Audit 2024-01-01.log")).unwrap();
+        let (content, _) = chained_file(ZERO_HASH, &["login"]);
+        fs::write(log_directory.join("Audit 2024-01-02.log"), [content.as_bytes(), b"\xff\xfe\n"].concat()).unwrap();
+        let violations = verify_hash_chain(&log_directory, "Audit").unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        let kinds: Vec<(usize, ChainViolationKind)> = violations.into_iter().map(|v| (v.line, v.kind)).collect();
+        assert_eq!(kinds, [(0, ChainViolationKind::Unreadable), (3, ChainViolationKind::Unchained)]);
+    }
+
+    #[test]
+    fn records_with_crlf_line_breaks_are_verified() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_chain_crlf_{}", std::process::id()));
+        fs::create_dir_all(&log_directory).unwrap();
+        let (content, _) = chained_file(ZERO_HASH, &["request failed\r\ncaused by: timeout\r", "retrying"]);
+        fs::write(log_directory.join("Audit 2024-01-01.log"), &content).unwrap();
+        let violations = verify_hash_chain(&log_directory, "Audit").unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(violations, []);
+    }
+}
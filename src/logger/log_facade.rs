@@ -0,0 +1,121 @@
+use crate::logger::error::TheLoggerError;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warning,
+            log::Level::Info => LogLevel::Information,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace
+        }
+    }
+}
+
+/// Backend for the log crate facade. Records are logged with the level mapped from the log crate's level, and the
+/// module path, file and line of the record, falling back to its module path when the file is unknown.
+impl log::Log for TheLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        !self.is_shut_down()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let file = record.file()
+            .or(record.module_path())
+            .unwrap_or(record.target());
+        self.log_blocking(
+            record.level().into(),
+            record.module_path().unwrap_or_default(),
+            (file, record.line().unwrap_or(0), 0),
+            &record.args().to_string()
+        );
+    }
+
+    fn flush(&self) {
+        self.flush_blocking();
+    }
+}
+
+impl TheLogger {
+    /// ## Description
+    /// Installs the logger as the global backend of the log crate facade, so that every record emitted through it,
+    /// including the ones from dependencies, is written into this logger's file. Records above `max_level` are
+    /// discarded by the facade before reaching the logger.
+    ///
+    /// Returns [`TheLoggerError::GlobalLoggerAlreadySet`] if another backend was already installed.
+    ///
+    /// Log levels are mapped as follows: Error to Error, Warn to Warning, Info to Information, Debug to Debug and Trace
+    /// to Trace.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{TheLogger, TheLoggerError};
+    ///
+    /// fn init_logger() -> Result<(), TheLoggerError> {
+    ///     TheLogger::instance().install_log_facade(log::LevelFilter::Info)?;
+    ///
+    ///     log::info!("This record is written by the_logger");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn install_log_facade(&'static self, max_level: log::LevelFilter) -> Result<(), TheLoggerError> {
+        log::set_logger(self).map_err(|_| TheLoggerError::GlobalLoggerAlreadySet)?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::Log;
+    use super::*;
+
+    #[test]
+    fn records_are_logged_with_the_mapped_level() {
+        let capture = TheLogger::capture();
+        let levels = [
+            (log::Level::Error, LogLevel::Error),
+            (log::Level::Warn, LogLevel::Warning),
+            (log::Level::Info, LogLevel::Information),
+            (log::Level::Debug, LogLevel::Debug),
+            (log::Level::Trace, LogLevel::Trace)
+        ];
+        for (level, _) in levels {
+            TheLogger::instance().log(
+                &log::Record::builder()
+                    .level(level)
+                    .module_path(Some("app::db"))
+                    .file(Some("src/db.rs"))
+                    .line(Some(42))
+                    .args(format_args!("facade record"))
+                    .build()
+            );
+        }
+
+        let records = capture.records();
+        let logged_levels: Vec<LogLevel> = records.iter().map(|record| record.level).collect();
+        assert_eq!(logged_levels, levels.map(|(_, level)| level));
+        assert!(records.iter().all(|record| record.message.contains("src/db.rs") && record.message.contains("42")));
+        assert!(records.iter().all(|record| record.message.ends_with("facade record")));
+    }
+
+    #[test]
+    fn the_module_path_is_the_location_of_records_without_a_file() {
+        let capture = TheLogger::capture();
+        TheLogger::instance().log(
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .module_path(Some("app::db"))
+                .args(format_args!("facade record"))
+                .build()
+        );
+
+        assert!(capture.records()[0].message.contains("app::db"));
+    }
+}
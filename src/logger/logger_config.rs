@@ -1,16 +1,32 @@
 
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::logger::error::FailureStrategy;
+#[cfg(feature = "encryption")]
+use crate::logger::encryption::EncryptionKey;
+#[cfg(feature = "redaction")]
+use crate::logger::redaction::RedactionRule;
+
 /// Constant to define the initial log text content maximum length. Customizable by config
 const LOG_CONTENT_INITIAL_LENGTH: usize = 300;
 
 /// Constant to define the initial location text content maximum length. Customizable by config
 const LOCATION_CONTENT_INITIAL_LENGTH: usize = 60;
 
+/// Constant to define the initial directory where the log files are created. Customizable by config
+const LOG_DIRECTORY_INITIAL_PATH: &str = "./logs/";
+
+/// Constant to define the initial name prefix of the log files, followed by the date. Customizable by config
+const LOG_FILE_INITIAL_PREFIX: &str = "Log";
+
 #[derive(Debug)]
 /// TheLoggerConfig allows the user to configure on startup the logger's output.
 pub struct TheLoggerConfig {
     date_config: TheDateConfig,
     time_config: TheTimeConfig,
     misc_config: TheMiscConfig,
+    output_config: TheOutputConfig,
+    filter_config: TheFilterConfig,
     log_level: LogLevel
 }
 
@@ -41,18 +57,146 @@ struct TheTimeConfig {
 /// - Show the column number (hidden by default)
 /// - Configure the location text content's maximum length
 /// - Configure the log text content's maximum length
+/// - Show a marker with the amount of bytes cut off when the log text content is truncated (hidden by default)
+/// - Configure whether the location shows the file path, the module path or both
+/// - Configure how the location is truncated when it's longer than its maximum length
+/// - Configure how messages containing line breaks are written
+/// - Show the thread name, thread id, tokio task id, process id and hostname (all hidden by default). The hostname
+///   needs the `hostname` feature
 struct TheMiscConfig {
     hide_level: bool,
     hide_file_name: bool,
     hide_file_line: bool,
     show_file_column: bool,
     location_length: usize,
-    log_content_length: usize
+    log_content_length: usize,
+    show_truncation_marker: bool,
+    location_source: LocationSource,
+    location_truncation: LocationTruncation,
+    multiline_mode: MultilineMode,
+    show_thread_name: bool,
+    show_thread_id: bool,
+    show_task_id: bool,
+    show_pid: bool,
+    #[cfg(feature = "hostname")]
+    show_hostname: bool
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+/// Sources of the location shown in each record. The module path is captured by the log macros, and it's shorter than
+/// the file path in deep workspaces, where the file path can get truncated by the location content's length
+pub enum LocationSource {
+    /// Shows the file path, as in "@src/handlers/user.rs: 42". This is the default
+    #[default]
+    FilePath,
+    /// Shows the module path, as in "@my_service::handlers::user: 42". Falls back to the file path when the module
+    /// path is unknown, as when logging with [`TheLogger::log_in_file`](crate::TheLogger::log_in_file)
+    ModulePath,
+    /// Shows the module path followed by the file path, as in "@my_service::handlers::user src/handlers/user.rs: 42"
+    Both
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+/// Strategies available to truncate the location when it's longer than the location content's maximum length
+pub enum LocationTruncation {
+    /// Keeps the start of the location, as in "@src/handlers/us". This is the default
+    #[default]
+    KeepHead,
+    /// Keeps the end of the location, which holds the file name and line, as in "…/user/handler.rs: 42"
+    KeepTail,
+    /// Keeps the first directory and the end of the location, eliding the middle, as in "@src/…/handler.rs: 42"
+    ElideMiddle,
+    /// Strips the prefix from the file path, such as the workspace root, before building the location. If the
+    /// location is still longer than its maximum length, its end is kept as in KeepTail
+    StripPrefix(String)
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+/// Modes available to write messages containing line breaks, such as pretty-printed structs or backtraces, which
+/// would otherwise break the one record per line layout of the log file
+pub enum MultilineMode {
+    /// Writes the message as it is. This is the default
+    #[default]
+    Raw,
+    /// Escapes the line breaks as "\\n" and "\\r", keeping the whole record in a single line. Backslashes are escaped
+    /// as "\\\\", so the escaped line breaks can be told apart from the content
+    Escape,
+    /// Indents the continuation lines with whitespace, so they're aligned with the content under the header
+    Indent,
+    /// Prefixes every continuation line with the same header as the first one: date, time, level and location
+    RepeatHeader
+}
+
+#[derive(Default, Debug)]
+/// Output configuration section, that includes the ability to:
+/// - Configure the flush policy of the buffered file writer
+/// - Sync the log file to disk when the logger is shut down (disabled by default)
+/// - Configure how failures to write into the log file are handled
+/// - Configure the directory where the log files are created
+/// - Configure the name prefix of the log files
+/// - Configure the Unix permissions of the log files and directories when they're created
+/// - Chain every record to the previous one with its hash, with the `audit` feature (disabled by default)
+/// - Encrypt the records at rest with a symmetric key, with the `encryption` feature (disabled by default)
+struct TheOutputConfig {
+    log_directory: PathBuf,
+    log_file_prefix: String,
+    flush_policy: FlushPolicy,
+    fsync_on_shutdown: bool,
+    failure_strategy: FailureStrategy,
+    file_mode: Option<u32>,
+    directory_mode: Option<u32>,
+    #[cfg(feature = "audit")]
+    hash_chain: bool,
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<EncryptionKey>
+}
+
+#[derive(Default, Debug)]
+/// Filter configuration section, that includes the ability to:
+/// - Rate limit the records of every log macro call site, with a default window per log level
+/// - Collapse consecutive identical records into a single "repeated N times" record (disabled by default)
+/// - Sample the records of each log level, keeping 1 in N or a random fraction of them (disabled by default)
+/// - Redact secrets and personal data from the records, with the `redaction` feature (disabled by default)
+struct TheFilterConfig {
+    rate_limits: [Option<Duration>; LOG_LEVELS],
+    deduplication_timeout: Option<Duration>,
+    samplings: [Option<Sampling>; LOG_LEVELS],
+    #[cfg(feature = "redaction")]
+    redaction_rules: Vec<RedactionRule>
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Strategies available to sample the records of a log level, so high-volume levels such as Trace can be left enabled
+/// in production at a low cost. Sampled-out records are discarded before their message is formatted
+pub enum Sampling {
+    /// Keeps the first of every N records of the level, counted across every call site. 0 and 1 keep every record
+    OneIn(u64),
+    /// Keeps each record of the level with the given probability, from 0.0 (none) to 1.0 (all)
+    Probability(f64)
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+/// Policies available to decide when the buffered file writer is flushed to the log file. Flushing more often is
+/// safer in case of a crash, flushing less often allows a higher throughput
+pub enum FlushPolicy {
+    /// Flushes after every record. This is the default, and behaves like an unbuffered writer
+    #[default]
+    EveryRecord,
+    /// Flushes once every N records have been written
+    EveryRecords(usize),
+    /// Flushes at most N milliseconds after a record is buffered. If no other record is written by then, a background
+    /// thread flushes it, started the first time a record is buffered
+    EveryMillis(u64),
+    /// Flushes only when an Error or Critical record is written, or when the buffer is full
+    OnErrorOrCritical
+}
+
+/// Amount of log levels, used to size the per level configurations
+pub(super) const LOG_LEVELS: usize = 7;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 /// 7 different types of log levels to allow the user to use the log n any way they need to
-pub(super) enum LogLevel {
+pub enum LogLevel {
     #[default]
     Verbose,
     Information,
@@ -334,6 +478,305 @@ impl TheLoggerConfig {
         self
     }
 
+    /// ## Description
+    /// Configures how messages containing line breaks are written. Default is to write them as they are
+    pub fn multiline_mode(mut self, mode: MultilineMode) -> Self {
+        self.misc_config.multiline_mode = mode;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to append a marker with the amount of bytes cut off when the content is truncated, as in
+    /// "…[truncated 1234 bytes]". Default is to hide it
+    pub fn show_truncation_marker(mut self) -> Self {
+        self.misc_config.show_truncation_marker = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to truncate the content without a marker. Default is to hide it
+    pub fn hide_truncation_marker(mut self) -> Self {
+        self.misc_config.show_truncation_marker = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures whether the location shows the file path, the module path or both. Default is the file path
+    pub fn location_source(mut self, source: LocationSource) -> Self {
+        self.misc_config.location_source = source;
+        self
+    }
+
+    /// ## Description
+    /// Configures how the location is truncated when it's longer than the location content's length. Default is to
+    /// keep its start
+    pub fn location_truncation(mut self, truncation: LocationTruncation) -> Self {
+        self.misc_config.location_truncation = truncation;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the name of the thread that logged the record. Default is to hide it
+    pub fn show_thread_name(mut self) -> Self {
+        self.misc_config.show_thread_name = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the name of the thread that logged the record. Default is to hide it
+    pub fn hide_thread_name(mut self) -> Self {
+        self.misc_config.show_thread_name = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the thread that logged the record. Default is to hide it
+    pub fn show_thread_id(mut self) -> Self {
+        self.misc_config.show_thread_id = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the thread that logged the record. Default is to hide it
+    pub fn hide_thread_id(mut self) -> Self {
+        self.misc_config.show_thread_id = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the tokio task that logged the record. Default is to hide it
+    pub fn show_task_id(mut self) -> Self {
+        self.misc_config.show_task_id = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the tokio task that logged the record. Default is to hide it
+    pub fn hide_task_id(mut self) -> Self {
+        self.misc_config.show_task_id = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the process. Default is to hide it
+    pub fn show_pid(mut self) -> Self {
+        self.misc_config.show_pid = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the process. Default is to hide it
+    pub fn hide_pid(mut self) -> Self {
+        self.misc_config.show_pid = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the hostname of the machine, with the `hostname` feature (disabled by default).
+    /// Default is to hide it
+    #[cfg(feature = "hostname")]
+    pub fn show_hostname(mut self) -> Self {
+        self.misc_config.show_hostname = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the hostname of the machine. Default is to hide it
+    #[cfg(feature = "hostname")]
+    pub fn hide_hostname(mut self) -> Self {
+        self.misc_config.show_hostname = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the directory where the log files are created. It'll be created if it doesn't exist. Default is
+    /// ./logs/
+    pub fn log_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output_config.log_directory = path.into();
+        self
+    }
+
+    /// ## Description
+    /// Configures the name of the log files, which will be followed by the date. Useful to keep the logs of
+    /// different logger instances in separate files. Default is "Log", as in "Log 2023-12-16.log"
+    pub fn log_file_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.output_config.log_file_prefix = prefix.into();
+        self
+    }
+
+    /// ## Description
+    /// Configures when the buffered file writer is flushed. Default is to flush after every record
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.output_config.flush_policy = policy;
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to sync the log file to disk after the final flush when it's shut down. Default is to
+    /// leave the sync to the operating system
+    pub fn enable_fsync_on_shutdown(mut self) -> Self {
+        self.output_config.fsync_on_shutdown = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to leave the log file sync to the operating system when it's shut down. This is the
+    /// default
+    pub fn disable_fsync_on_shutdown(mut self) -> Self {
+        self.output_config.fsync_on_shutdown = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures how the logger handles a failure to write into the log file, such as a full disk or a read-only
+    /// directory. Default is to fall back to stderr
+    pub fn on_failure(mut self, strategy: FailureStrategy) -> Self {
+        self.output_config.failure_strategy = strategy;
+        self
+    }
+
+    /// ## Description
+    /// Configures the Unix permissions of the log files when they're created, such as 0o640 to keep them out of reach
+    /// of other users. The process' umask still applies, and existing files are left untouched. Ignored on other
+    /// platforms. Default is the system's default permissions, usually 0o644
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.output_config.file_mode = Some(mode);
+        self
+    }
+
+    /// ## Description
+    /// Configures the Unix permissions of the logs directory and its missing parents when they're created, such as
+    /// 0o750. The process' umask still applies, and existing directories are left untouched. Ignored on other
+    /// platforms. Default is the system's default permissions, usually 0o755
+    pub fn directory_mode(mut self, mode: u32) -> Self {
+        self.output_config.directory_mode = Some(mode);
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to make the log files tamper-evident for audit trails: every record ends with a
+    /// " #hash=" suffix holding the SHA-256 of the previous record's hash followed by the record, and every file starts
+    /// with a header seeding its chain with the final hash of the previous file. Modified, removed or reordered
+    /// records are detected by [`verify_hash_chain`](crate::verify_hash_chain). Encrypted log files aren't hash
+    /// chained: the encryption chains every record to the previous one in the file instead, see `enable_encryption`.
+    /// Default is to write the records without hashes.
+    ///
+    /// The chain isn't keyed, so it only detects accidental or naive edits: anyone who can write the files can also
+    /// recompute every hash after the edited record. Removing the last records of the newest file, or the newest files
+    /// entirely, isn't detected either, since no later hash depends on them. Against a deliberate attacker, use
+    /// `enable_encryption` with a secret key, from the `encryption` feature, to detect modified records, and ship the
+    /// records to a write-once store to detect removed ones
+    #[cfg(feature = "audit")]
+    pub fn enable_hash_chain(mut self) -> Self {
+        self.output_config.hash_chain = true;
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write the records without hashes. This is the default
+    #[cfg(feature = "audit")]
+    pub fn disable_hash_chain(mut self) -> Self {
+        self.output_config.hash_chain = false;
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to encrypt the records at rest with the key, using XChaCha20-Poly1305 authenticated
+    /// encryption. The records are written into "<prefix> <date>.log.enc" files as length-framed ciphertexts, and
+    /// each time the file is opened a new segment with its own random nonce is started. Every record is authenticated
+    /// along with the tag of the previous one in the file, so modified, removed or reordered records and segments are
+    /// detected when the file is read with [`EncryptedLogReader`](crate::EncryptedLogReader). As with the hash chain,
+    /// which isn't written into encrypted files, removing the last records of a file or whole files isn't detected.
+    /// Default is to write the records as plain text
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(mut self, key: EncryptionKey) -> Self {
+        self.output_config.encryption_key = Some(key);
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write the records as plain text. This is the default
+    #[cfg(feature = "encryption")]
+    pub fn disable_encryption(mut self) -> Self {
+        self.output_config.encryption_key = None;
+        self
+    }
+
+    /// ## Description
+    /// Configures the default rate limit of the log level: every log macro call site writes at most one record of the
+    /// level per window, and summarizes the suppressed ones as "suppressed N similar messages" when the window ends, or
+    /// when the logger is flushed or shut down. The `every = <window>;` clause of the log macros overrides it for a
+    /// single call site. Default is no rate limit
+    pub fn rate_limit(mut self, level: LogLevel, window: Duration) -> Self {
+        self.filter_config.rate_limits[level as usize] = Some(window);
+        self
+    }
+
+    /// ## Description
+    /// Removes the default rate limit of the log level. This is the default
+    pub fn no_rate_limit(mut self, level: LogLevel) -> Self {
+        self.filter_config.rate_limits[level as usize] = None;
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to collapse consecutive identical records, with the same level, location and content, as
+    /// syslog's "last message repeated" does. The first record is written as usual, and the identical ones that follow
+    /// are written as a single record with a "[repeated N times]" suffix when a different record arrives or the timeout
+    /// passes, or when the logger is flushed. Default is to write every record
+    pub fn deduplicate(mut self, timeout: Duration) -> Self {
+        self.filter_config.deduplication_timeout = Some(timeout);
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write every record, including identical consecutive ones. This is the default
+    pub fn no_deduplication(mut self) -> Self {
+        self.filter_config.deduplication_timeout = None;
+        self
+    }
+
+    /// ## Description
+    /// Configures the sampling of the log level, keeping only 1 in N records or a random fraction of them. Records
+    /// logged with the log macros are sampled before their message is formatted. Default is to keep every record
+    pub fn sample(mut self, level: LogLevel, sampling: Sampling) -> Self {
+        self.filter_config.samplings[level as usize] = Some(sampling);
+        self
+    }
+
+    /// ## Description
+    /// Removes the sampling of the log level, keeping every record. This is the default
+    pub fn no_sampling(mut self, level: LogLevel) -> Self {
+        self.filter_config.samplings[level as usize] = None;
+        self
+    }
+
+    /// ## Description
+    /// Adds a rule to redact secrets and personal data from the log content and structured fields before they're
+    /// written, replacing each match with "***". Rules are applied in the order they were added. Default is to write
+    /// every record as it is
+    #[cfg(feature = "redaction")]
+    pub fn redact(mut self, rule: RedactionRule) -> Self {
+        self.filter_config.redaction_rules.push(rule);
+        self
+    }
+
+    /// ## Description
+    /// Adds the built-in redaction rules: bearer tokens, passwords in URLs, credit card numbers and emails
+    #[cfg(feature = "redaction")]
+    pub fn redact_builtin_patterns(mut self) -> Self {
+        self.filter_config.redaction_rules.extend(RedactionRule::builtin());
+        self
+    }
+
+    /// ## Description
+    /// Removes every redaction rule, writing the records as they are. This is the default
+    #[cfg(feature = "redaction")]
+    pub fn no_redaction(mut self) -> Self {
+        self.filter_config.redaction_rules.clear();
+        self
+    }
+
     /////////////
     /* Getters */
     /////////////
@@ -417,6 +860,120 @@ impl TheLoggerConfig {
         self.misc_config.log_content_length
     }
 
+    #[doc(hidden)]
+    pub(super) fn get_truncation_marker_config(&self) -> bool {
+        self.misc_config.show_truncation_marker
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_location_source(&self) -> LocationSource {
+        self.misc_config.location_source
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_location_truncation(&self) -> &LocationTruncation {
+        &self.misc_config.location_truncation
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_multiline_mode(&self) -> MultilineMode {
+        self.misc_config.multiline_mode
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_thread_name_config(&self) -> bool {
+        self.misc_config.show_thread_name
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_thread_id_config(&self) -> bool {
+        self.misc_config.show_thread_id
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_task_id_config(&self) -> bool {
+        self.misc_config.show_task_id
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_pid_config(&self) -> bool {
+        self.misc_config.show_pid
+    }
+
+    #[cfg(feature = "hostname")]
+    #[doc(hidden)]
+    pub(super) fn get_hostname_config(&self) -> bool {
+        self.misc_config.show_hostname
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_log_directory(&self) -> &Path {
+        &self.output_config.log_directory
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_log_file_prefix(&self) -> &str {
+        &self.output_config.log_file_prefix
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_flush_policy(&self) -> FlushPolicy {
+        self.output_config.flush_policy
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_fsync_on_shutdown_config(&self) -> bool {
+        self.output_config.fsync_on_shutdown
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_failure_strategy(&self) -> FailureStrategy {
+        self.output_config.failure_strategy.clone()
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_file_mode(&self) -> Option<u32> {
+        self.output_config.file_mode
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_directory_mode(&self) -> Option<u32> {
+        self.output_config.directory_mode
+    }
+
+    #[cfg(feature = "audit")]
+    #[doc(hidden)]
+    pub(super) fn get_hash_chain_config(&self) -> bool {
+        self.output_config.hash_chain
+    }
+
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    pub(super) fn get_encryption_key(&self) -> Option<&EncryptionKey> {
+        self.output_config.encryption_key.as_ref()
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_rate_limit(&self, level: LogLevel) -> Option<Duration> {
+        self.filter_config.rate_limits[level as usize]
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_deduplication_timeout(&self) -> Option<Duration> {
+        self.filter_config.deduplication_timeout
+    }
+
+    #[doc(hidden)]
+    pub(super) fn get_sampling(&self, level: LogLevel) -> Option<Sampling> {
+        self.filter_config.samplings[level as usize]
+    }
+
+    #[cfg(feature = "redaction")]
+    #[doc(hidden)]
+    pub(super) fn get_redaction_rules(&self) -> &[RedactionRule] {
+        &self.filter_config.redaction_rules
+    }
+
     /////////////
     /* Setters */
     /////////////
@@ -499,6 +1056,126 @@ impl TheLoggerConfig {
     pub(super) fn set_log_content_length(&mut self, data: usize) {
         self.misc_config.log_content_length = data;
     }
+
+    #[doc(hidden)]
+    pub(super) fn set_truncation_marker_config(&mut self, data: bool) {
+        self.misc_config.show_truncation_marker = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_location_source(&mut self, data: LocationSource) {
+        self.misc_config.location_source = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_location_truncation(&mut self, data: LocationTruncation) {
+        self.misc_config.location_truncation = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_multiline_mode(&mut self, data: MultilineMode) {
+        self.misc_config.multiline_mode = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_thread_name_config(&mut self, data: bool) {
+        self.misc_config.show_thread_name = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_thread_id_config(&mut self, data: bool) {
+        self.misc_config.show_thread_id = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_task_id_config(&mut self, data: bool) {
+        self.misc_config.show_task_id = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_pid_config(&mut self, data: bool) {
+        self.misc_config.show_pid = data;
+    }
+
+    #[cfg(feature = "hostname")]
+    #[doc(hidden)]
+    pub(super) fn set_hostname_config(&mut self, data: bool) {
+        self.misc_config.show_hostname = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_log_directory(&mut self, data: PathBuf) {
+        self.output_config.log_directory = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_log_file_prefix(&mut self, data: String) {
+        self.output_config.log_file_prefix = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_flush_policy(&mut self, data: FlushPolicy) {
+        self.output_config.flush_policy = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_fsync_on_shutdown_config(&mut self, data: bool) {
+        self.output_config.fsync_on_shutdown = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_failure_strategy(&mut self, data: FailureStrategy) {
+        self.output_config.failure_strategy = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_file_mode(&mut self, data: Option<u32>) {
+        self.output_config.file_mode = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_directory_mode(&mut self, data: Option<u32>) {
+        self.output_config.directory_mode = data;
+    }
+
+    #[cfg(feature = "audit")]
+    #[doc(hidden)]
+    pub(super) fn set_hash_chain_config(&mut self, data: bool) {
+        self.output_config.hash_chain = data;
+    }
+
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    pub(super) fn set_encryption_key(&mut self, data: Option<EncryptionKey>) {
+        self.output_config.encryption_key = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_rate_limit(&mut self, level: LogLevel, data: Option<Duration>) {
+        self.filter_config.rate_limits[level as usize] = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_deduplication_timeout(&mut self, data: Option<Duration>) {
+        self.filter_config.deduplication_timeout = data;
+    }
+
+    #[doc(hidden)]
+    pub(super) fn set_sampling(&mut self, level: LogLevel, data: Option<Sampling>) {
+        self.filter_config.samplings[level as usize] = data;
+    }
+
+    #[cfg(feature = "redaction")]
+    #[doc(hidden)]
+    pub(super) fn set_redaction_rules(&mut self, data: Vec<RedactionRule>) {
+        self.filter_config.redaction_rules = data;
+    }
+
+    #[cfg(feature = "redaction")]
+    #[doc(hidden)]
+    pub(super) fn add_redaction_rules(&mut self, data: Vec<RedactionRule>) {
+        self.filter_config.redaction_rules.extend(data);
+    }
 }
 
 impl Default for TheLoggerConfig {
@@ -512,6 +1189,12 @@ impl Default for TheLoggerConfig {
                 log_content_length: LOG_CONTENT_INITIAL_LENGTH,
                 ..Default::default()
             },
+            output_config: TheOutputConfig {
+                log_directory: PathBuf::from(LOG_DIRECTORY_INITIAL_PATH),
+                log_file_prefix: LOG_FILE_INITIAL_PREFIX.to_string(),
+                ..Default::default()
+            },
+            filter_config: TheFilterConfig::default(),
             log_level: LogLevel::Verbose
         }
     }
@@ -4,7 +4,12 @@
 /// Logs a verbose log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -16,8 +21,11 @@
 /// }
 /// ```
 macro_rules! log {
-    ($logger:expr, $($msg:tt)*) => {
-        $logger.verbose().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Verbose, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Verbose, None, $($rest)*);
     };
 }
 
@@ -25,7 +33,12 @@ macro_rules! log {
 /// Logs an informational log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -34,13 +47,16 @@ macro_rules! log {
 /// async fn exec_log() {
 ///   let logger = TheLogger::instance();
 ///   log_info!(logger, "This is an informational log message");
+///   log_info!(logger, user_id = 42, path = %"/users"; "This is an informational log message with fields");
 /// }
 /// ```
 #[macro_export]
 macro_rules! log_info {
-    ($logger:expr, $($msg:tt)*) => {
-
-        $logger.info().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Information, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Information, None, $($rest)*);
     };
 }
 
@@ -48,7 +64,12 @@ macro_rules! log_info {
 /// Logs an error log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -61,8 +82,11 @@ macro_rules! log_info {
 /// ```
 #[macro_export]
 macro_rules! log_error {
-    ($logger:expr, $($msg:tt)*) => {
-        $logger.error().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Error, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Error, None, $($rest)*);
     };
 }
 
@@ -70,7 +94,12 @@ macro_rules! log_error {
 /// Logs a warning log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -79,13 +108,16 @@ macro_rules! log_error {
 /// async fn exec_log() {
 ///   let logger = TheLogger::instance();
 ///   log_warning!(logger, "This is a warning log message");
+///   log_warning!(logger, every = 10s; "This warning is written at most once every 10 seconds");
 /// }
 /// ```
 #[macro_export]
 macro_rules! log_warning {
-    ($logger:expr, $($msg:tt)*) => {
-
-        $logger.warning().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Warning, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Warning, None, $($rest)*);
     };
 }
 
@@ -93,7 +125,12 @@ macro_rules! log_warning {
 /// Logs a debug log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -106,9 +143,11 @@ macro_rules! log_warning {
 /// ```
 #[macro_export]
 macro_rules! log_debug {
-    ($logger:expr, $($msg:tt)*) => {
-
-        $logger.debug().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Debug, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Debug, None, $($rest)*);
     };
 }
 
@@ -116,7 +155,12 @@ macro_rules! log_debug {
 /// Logs a trace log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -129,9 +173,11 @@ macro_rules! log_debug {
 /// ```
 #[macro_export]
 macro_rules! log_trace {
-    ($logger:expr, $($msg:tt)*) => {
-
-        $logger.trace().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Trace, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Trace, None, $($rest)*);
     };
 }
 
@@ -139,7 +185,12 @@ macro_rules! log_trace {
 /// Logs a critical log message in the log file.
 ///
 /// ### Parameters
-/// A reference to TheLogger's instance and the log message itself
+/// A reference to TheLogger's instance and the log message itself, optionally preceded by structured fields in the
+/// `key = value, key = %value, key = ?value;` form. See [`FieldValue`](crate::FieldValue) for details.
+///
+/// The fields can be preceded by an `every = <window>;` clause to rate limit the call site, with a window in ms, s, m
+/// or h. See [`TheLoggerConfig::rate_limit`](crate::TheLoggerConfig::rate_limit) for details. Hence `every` can't be
+/// used as the first field's name
 ///
 /// ### Example
 /// ```rust
@@ -152,8 +203,189 @@ macro_rules! log_trace {
 /// ```
 #[macro_export]
 macro_rules! log_critical {
-    ($logger:expr, $($msg:tt)*) => {
+    ($logger:expr, every = $window:tt; $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Critical, Some($crate::__the_logger_log!(@window $window)), $($rest)*);
+    };
+    ($logger:expr, $($rest:tt)*) => {
+        $crate::__the_logger_log!(@start $logger, Critical, None, $($rest)*);
+    };
+}
 
-        $logger.critical().await.log_in_file((file!(), line!(), column!()), &format!($($msg)*)).await;
+/// ## Description
+/// Logs an error and its full chain of sources as an error log message in the log file, each source in its own
+/// "caused by:" line. See [`TheLogger::log_error_chain_in_file`](crate::TheLogger::log_error_chain_in_file) for
+/// details.
+///
+/// ### Parameters
+/// A reference to TheLogger's instance and a reference to the error, optionally followed by a message that gives
+/// context to the error, with built-in support for the format! macro
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{log_error_chain, TheLogger};
+///
+/// async fn load_config(user_id: u32) {
+///   let logger = TheLogger::instance();
+///   if let Err(error) = std::fs::read_to_string("config.toml") {
+///     log_error_chain!(logger, &error);
+///     log_error_chain!(logger, &error, "while loading the config of user {}", user_id);
+///   }
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_error_chain {
+    ($logger:expr, $error:expr) => {
+        $logger.log_error_chain_in_file(
+            module_path!(),
+            (file!(), line!(), column!()),
+            None,
+            $error
+        ).await;
+    };
+    ($logger:expr, $error:expr, $($msg:tt)+) => {
+        $logger.log_error_chain_in_file(
+            module_path!(),
+            (file!(), line!(), column!()),
+            Some(&format!($($msg)+)),
+            $error
+        ).await;
+    };
+}
+
+/// ## Description
+/// Starts a span that logs a debug record now, and another one with the elapsed time when the returned
+/// [`TheLoggerSpan`](crate::TheLoggerSpan) guard is dropped. Unlike [`TheLogger::span`](crate::TheLogger::span), the
+/// records show the module path of the call site. Nested spans are started with
+/// [`TheLoggerSpan::child`](crate::TheLoggerSpan::child).
+///
+/// ### Parameters
+/// A reference to TheLogger's instance and the name of the span
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{log_span, TheLogger};
+///
+/// async fn load_user(user_id: u32) {
+///   let logger = TheLogger::instance();
+///   let span = log_span!(logger, "load_user");
+///   let _query_span = span.child("query_db");
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_span {
+    ($logger:expr, $name:expr) => {
+        $logger.span_in_module(module_path!(), $name)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// Shared implementation of the log macros. Structured fields are munched one at a time until the ';' that starts the
+/// log message, so that each value can be captured with its From implementation (`key = value`), its Display
+/// implementation (`key = %value`) or its Debug implementation (`key = ?value`). Each expansion declares the static
+/// call site used to rate limit it, and the sampling and rate limit are checked before the message is formatted
+macro_rules! __the_logger_log {
+    (@start $logger:expr, $level:ident, $window:expr, $key:ident = $($rest:tt)+) => {
+        $crate::__the_logger_log!(@fields $logger, $level, $window, [] $key = $($rest)+)
+    };
+    (@start $logger:expr, $level:ident, $window:expr, $($msg:tt)*) => {
+        $crate::__the_logger_log!(@log $logger, $level, $window, [] $($msg)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = % $value:expr ; $($msg:tt)*) => {
+        $crate::__the_logger_log!(@log $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::display(&$value))] $($msg)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = % $value:expr , $($rest:tt)*) => {
+        $crate::__the_logger_log!(@fields $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::display(&$value))] $($rest)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = ? $value:expr ; $($msg:tt)*) => {
+        $crate::__the_logger_log!(@log $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::debug(&$value))] $($msg)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = ? $value:expr , $($rest:tt)*) => {
+        $crate::__the_logger_log!(@fields $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::debug(&$value))] $($rest)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = $value:expr ; $($msg:tt)*) => {
+        $crate::__the_logger_log!(@log $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::from($value))] $($msg)*)
+    };
+    (@fields $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $key:ident = $value:expr , $($rest:tt)*) => {
+        $crate::__the_logger_log!(@fields $logger, $level, $window, [$($fields,)* (stringify!($key), $crate::FieldValue::from($value))] $($rest)*)
+    };
+    (@log $logger:expr, $level:ident, $window:expr, [$($fields:expr),*] $($msg:tt)*) => {{
+        static CALL_SITE: $crate::CallSite = $crate::CallSite::new();
+        let logger: &$crate::TheLogger = &$logger;
+        let level = $crate::LogLevel::$level;
+        let location = (file!(), line!(), column!());
+        if logger.record_allowed(level, &CALL_SITE, $window, module_path!(), location).await {
+            logger.log_in_file_with_fields(level, module_path!(), location, &[$($fields),*], &format!($($msg)*)).await
+        }
+    }};
+    (@window $window:tt) => {{
+        const WINDOW: ::std::time::Duration = $crate::parse_rate_limit_window(stringify!($window));
+        WINDOW
+    }};
+}
+
+#[macro_export]
+/// ## Description
+/// Asserts that a record was logged on the current thread since the active
+/// [`TheLogger::capture`](crate::TheLogger::capture) was started, with the log level and containing the text. Either
+/// of them can be omitted. Panics listing the captured records if there's no such record, or if there's no active
+/// capture.
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{assert_logged, log_warning, TheLogger};
+///
+/// async fn warns_about_the_timeout() {
+///     let _capture = TheLogger::capture();
+///     log_warning!(TheLogger::instance(), "request timeout");
+///     assert_logged!(level = Warning, contains = "timeout");
+/// }
+/// ```
+macro_rules! assert_logged {
+    ($($filter:tt)+) => {
+        $crate::__the_logger_assert_captured!(true, $($filter)+)
+    };
+}
+
+#[macro_export]
+/// ## Description
+/// Asserts that no record was logged on the current thread since the active
+/// [`TheLogger::capture`](crate::TheLogger::capture) was started, with the log level and containing the text. Either
+/// of them can be omitted. Panics listing the captured records if there's such a record, or if there's no active
+/// capture.
+///
+/// ### Example
+/// ```rust
+/// use the_logger::{assert_not_logged, log_warning, TheLogger};
+///
+/// async fn doesnt_fail() {
+///     let _capture = TheLogger::capture();
+///     log_warning!(TheLogger::instance(), "request timeout");
+///     assert_not_logged!(level = Error);
+/// }
+/// ```
+macro_rules! assert_not_logged {
+    ($($filter:tt)+) => {
+        $crate::__the_logger_assert_captured!(false, $($filter)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// Shared implementation of [`assert_logged!`] and [`assert_not_logged!`], parsing their `level = <LogLevel variant>`
+/// and `contains = <text>` filters
+macro_rules! __the_logger_assert_captured {
+    ($expected:expr, level = $level:ident, contains = $text:expr $(,)?) => {
+        $crate::assert_captured(
+            Some($crate::LogLevel::$level),
+            Some(::std::convert::AsRef::<str>::as_ref(&$text)),
+            $expected
+        )
+    };
+    ($expected:expr, level = $level:ident $(,)?) => {
+        $crate::assert_captured(Some($crate::LogLevel::$level), None, $expected)
+    };
+    ($expected:expr, contains = $text:expr $(,)?) => {
+        $crate::assert_captured(None, Some(::std::convert::AsRef::<str>::as_ref(&$text)), $expected)
     };
 }
@@ -1,4 +1,22 @@
-pub mod the_logger;
-mod macros;
-pub mod logger_config;
-
+pub mod the_logger;
+mod macros;
+pub mod logger_config;
+pub mod error;
+pub mod fields;
+mod context;
+pub mod span;
+pub mod capture;
+mod panic_hook;
+mod error_chain;
+pub mod rate_limit;
+mod sampling;
+#[cfg(feature = "log_facade")]
+mod log_facade;
+#[cfg(feature = "tracing_layer")]
+pub mod tracing_layer;
+#[cfg(feature = "redaction")]
+pub mod redaction;
+#[cfg(feature = "audit")]
+pub mod hash_chain;
+#[cfg(feature = "encryption")]
+pub mod encryption;
\ No newline at end of file
@@ -0,0 +1,90 @@
+use std::backtrace::Backtrace;
+use std::time::Duration;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+/// Constant to define how long the panic hook waits for the logger to be available before giving up on the record
+const PANIC_HOOK_LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl TheLogger {
+    /// ## Description
+    /// Installs a panic hook that logs every panic as a critical record, with the panic message, location and thread
+    /// name, and optionally a backtrace captured regardless of the RUST_BACKTRACE variable. The log file is flushed
+    /// right after, and then the previously installed hook is called, which by default prints the panic to stderr.
+    /// Like any other record, it's limited by the log content's length, so increase it to keep complete backtraces.
+    ///
+    /// It doesn't need an async context, so panics are logged from any thread. If another thread keeps the logger busy
+    /// for more than a second, the record is skipped and the previous hook is called anyway. If the panic happened
+    /// while the logger was in use in the same thread, such as in a failure callback, the record is skipped right away.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::TheLogger;
+    ///
+    /// fn main() {
+    ///     TheLogger::instance().install_panic_hook(true);
+    ///     //  Every panic from now on is logged as CRITICAL, with its backtrace
+    /// }
+    /// ```
+    pub fn install_panic_hook(&'static self, capture_backtrace: bool) {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            let payload = panic_info.payload();
+            let message = payload.downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            let thread = std::thread::current();
+
+            let mut msg = format!("thread '{}' panicked: {}", thread.name().unwrap_or("unnamed"), message);
+            if capture_backtrace {
+                msg.push_str(format!("\nstack backtrace:\n{}", Backtrace::force_capture()).as_str());
+            }
+
+            let location = panic_info.location()
+                .map(|location| (location.file(), location.line(), location.column()))
+                .unwrap_or(("unknown", 0, 0));
+            self.log_and_flush_with_timeout(LogLevel::Critical, location, &msg, PANIC_HOOK_LOCK_TIMEOUT);
+
+            previous_hook(panic_info);
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FlushPolicy, TheLoggerConfig};
+    use super::*;
+
+    #[test]
+    fn panics_are_logged_and_flushed() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_panic_hook_{}", std::process::id()));
+        //  The records are only flushed by the hook, since the policy would wait for 100 of them
+        let config = TheLoggerConfig::default()
+            .log_directory(&log_directory)
+            .flush_policy(FlushPolicy::EveryRecords(100))
+            .log_content_length(100_000);
+        let logger: &'static TheLogger = Box::leak(Box::new(TheLogger::with_config(config).unwrap()));
+        //  The hook is global, so the original one is kept to be restored before other tests panic into this logger
+        type PanicHook = dyn Fn(&std::panic::PanicHookInfo<'_>) + Send + Sync;
+        let original_hook: std::sync::Arc<PanicHook> = std::panic::take_hook().into();
+        let previous_hook = original_hook.clone();
+        std::panic::set_hook(Box::new(move |panic_info| previous_hook(panic_info)));
+        logger.install_panic_hook(true);
+
+        let panicking_thread = std::thread::Builder::new()
+            .name("hooked".to_string())
+            .spawn(|| panic!("boom {}", 42))
+            .unwrap();
+        assert!(panicking_thread.join().is_err());
+        let _ = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic_info| original_hook(panic_info)));
+
+        let log_file = std::fs::read_dir(&log_directory).unwrap().next().unwrap().unwrap().path();
+        let records = std::fs::read_to_string(log_file).unwrap();
+        std::fs::remove_dir_all(&log_directory).unwrap();
+        assert!(records.contains("[CRITICAL]"));
+        assert!(records.contains("panic_hook.rs"));
+        assert!(records.contains("thread 'hooked' panicked: boom 42\nstack backtrace:\n"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[doc(hidden)]
+/// State of a single log macro call site, used to rate limit its records. Every log macro expansion declares its own
+/// static call site
+pub struct CallSite {
+    state: Mutex<CallSiteState>
+}
+
+#[doc(hidden)]
+struct CallSiteState {
+    window_start: Option<Instant>,
+    suppressed: u64
+}
+
+#[doc(hidden)]
+/// Outcome of checking a call site against its rate limit window
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum RateLimitDecision {
+    /// The record is written, after a summary of the records suppressed during the previous window if there were any
+    Log { suppressed: u64 },
+    /// The record is discarded, since the call site already logged within the current window. Holds the amount of
+    /// records suppressed so far within it, including this one
+    Suppress { suppressed: u64 }
+}
+
+#[doc(hidden)]
+/// Records suppressed by a call site whose summary wasn't written yet
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum SuppressedRecords {
+    /// The summary was already written, or the call site didn't suppress any record
+    None,
+    /// The summary of this amount of suppressed records is due, and must be written
+    Due(u64),
+    /// The summary is due when the current window ends at the given instant
+    Until(Instant)
+}
+
+impl CallSite {
+    #[doc(hidden)]
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(CallSiteState { window_start: None, suppressed: 0 })
+        }
+    }
+
+    #[doc(hidden)]
+    /// Allows the first record of each window and suppresses the rest. The window starts with the first allowed record,
+    /// and the amount of records suppressed within it is returned when the next one is allowed
+    pub(super) fn check(&self, window: Duration) -> RateLimitDecision {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        match state.window_start {
+            Some(window_start) if now.duration_since(window_start) < window => {
+                state.suppressed += 1;
+                RateLimitDecision::Suppress { suppressed: state.suppressed }
+            },
+            _ => {
+                let suppressed = state.suppressed;
+                state.window_start = Some(now);
+                state.suppressed = 0;
+                RateLimitDecision::Log { suppressed }
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    /// Takes the records suppressed within the current window if it ends by the given instant, or regardless of when
+    /// it ends without one, so their summary is written right away instead of before the next allowed record
+    pub(super) fn take_suppressed(&self, window: Duration, until: Option<Instant>) -> SuppressedRecords {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let window_end = match state.window_start {
+            Some(window_start) if state.suppressed > 0 => window_start + window,
+            _ => return SuppressedRecords::None
+        };
+        if until.is_some_and(|until| window_end > until) {
+            return SuppressedRecords::Until(window_end);
+        }
+        SuppressedRecords::Due(std::mem::take(&mut state.suppressed))
+    }
+}
+
+#[doc(hidden)]
+/// Returns the summary record of the records suppressed by a call site
+pub(super) fn suppressed_summary(suppressed: u64) -> String {
+    format!("suppressed {} similar messages", suppressed)
+}
+
+#[doc(hidden)]
+/// Parses the window of the `every = <window>;` clause of the log macros, such as "500ms", "10s", "5m" or "1h". It's
+/// evaluated in a const context by the macros, so an invalid window is reported at compile time
+pub const fn parse_rate_limit_window(window: &str) -> Duration {
+    let bytes = window.as_bytes();
+    let mut index = 0;
+    let mut amount: u64 = 0;
+    while index < bytes.len() && bytes[index].is_ascii_digit() {
+        amount = amount * 10 + (bytes[index] - b'0') as u64;
+        index += 1;
+    }
+    if index == 0 {
+        panic!("the rate limit window must start with an amount, as in every = 10s");
+    }
+
+    let unit_length = bytes.len() - index;
+    if unit_length == 2 && bytes[index] == b'm' && bytes[index + 1] == b's' {
+        Duration::from_millis(amount)
+    } else if unit_length == 1 && bytes[index] == b's' {
+        Duration::from_secs(amount)
+    } else if unit_length == 1 && bytes[index] == b'm' {
+        Duration::from_secs(amount * 60)
+    } else if unit_length == 1 && bytes[index] == b'h' {
+        Duration::from_secs(amount * 60 * 60)
+    } else {
+        panic!("the rate limit window must end with one of the units ms, s, m or h, as in every = 10s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_are_parsed_with_their_unit() {
+        assert_eq!(parse_rate_limit_window("500ms"), Duration::from_millis(500));
+        assert_eq!(parse_rate_limit_window("10s"), Duration::from_secs(10));
+        assert_eq!(parse_rate_limit_window("2m"), Duration::from_secs(120));
+        assert_eq!(parse_rate_limit_window("1h"), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn call_site_suppresses_records_within_the_window() {
+        let call_site = CallSite::new();
+        assert_eq!(call_site.check(Duration::from_secs(60)), RateLimitDecision::Log { suppressed: 0 });
+        assert_eq!(call_site.check(Duration::from_secs(60)), RateLimitDecision::Suppress { suppressed: 1 });
+        assert_eq!(call_site.check(Duration::from_secs(60)), RateLimitDecision::Suppress { suppressed: 2 });
+        //  A zero window has always ended, so the next record reports the ones suppressed before it
+        assert_eq!(call_site.check(Duration::ZERO), RateLimitDecision::Log { suppressed: 2 });
+        assert_eq!(call_site.check(Duration::ZERO), RateLimitDecision::Log { suppressed: 0 });
+    }
+
+    #[test]
+    fn suppressed_records_are_taken_once_the_window_ends() {
+        let call_site = CallSite::new();
+        assert_eq!(call_site.take_suppressed(Duration::from_secs(60), None), SuppressedRecords::None);
+        call_site.check(Duration::from_secs(60));
+        call_site.check(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(matches!(call_site.take_suppressed(Duration::from_secs(60), Some(now)), SuppressedRecords::Until(_)));
+        assert_eq!(call_site.take_suppressed(Duration::ZERO, Some(now)), SuppressedRecords::Due(1));
+        //  The taken records aren't reported again by the next allowed record
+        assert_eq!(call_site.check(Duration::ZERO), RateLimitDecision::Log { suppressed: 0 });
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::logger::logger_config::{LogLevel, Sampling, LOG_LEVELS};
+
+#[doc(hidden)]
+/// Keeps the state needed to sample the records of each log level: a counter per level for the 1-in-N sampling, and a
+/// xorshift generator for the probabilistic sampling. The generator isn't suitable for anything but sampling, which
+/// only needs it to be cheap and evenly distributed
+pub(super) struct Sampler {
+    counters: [AtomicU64; LOG_LEVELS],
+    random_state: AtomicU64
+}
+
+impl Sampler {
+    #[doc(hidden)]
+    /// Creates the sampler with a random seed, taken from the random keys of the standard library's hasher
+    pub(super) fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Self {
+            counters: Default::default(),
+            //  The xorshift generator gets stuck at zero, so it can't be used as seed
+            random_state: AtomicU64::new(seed | 1)
+        }
+    }
+
+    #[doc(hidden)]
+    /// Returns whether the record of the log level is kept by the sampling
+    pub(super) fn sample(&self, log_level: LogLevel, sampling: Sampling) -> bool {
+        match sampling {
+            Sampling::OneIn(records) => {
+                let counter = self.counters[log_level as usize].fetch_add(1, Ordering::Relaxed);
+                records <= 1 || counter.is_multiple_of(records)
+            },
+            Sampling::Probability(probability) => {
+                //  The 53 high bits of the random number fill the mantissa of a float in [0, 1)
+                let random = (self.next_random() >> 11) as f64 / (1u64 << 53) as f64;
+                random < probability
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    fn next_random(&self) -> u64 {
+        let xorshift = |mut state: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let previous = self.random_state
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| Some(xorshift(state)))
+            .unwrap_or_else(|state| state);
+        xorshift(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_in_n_sampling_keeps_the_first_record_of_every_n() {
+        let sampler = Sampler::new();
+        let kept: Vec<bool> = (0..7).map(|_| sampler.sample(LogLevel::Trace, Sampling::OneIn(3))).collect();
+        assert_eq!(kept, [true, false, false, true, false, false, true]);
+        //  Each level has its own counter
+        assert!(sampler.sample(LogLevel::Verbose, Sampling::OneIn(3)));
+    }
+
+    #[test]
+    fn probabilistic_sampling_keeps_about_the_given_fraction() {
+        let sampler = Sampler::new();
+        let kept = (0..100_000).filter(|_| sampler.sample(LogLevel::Trace, Sampling::Probability(0.1))).count();
+        assert!((9_000..11_000).contains(&kept), "kept {} records", kept);
+        assert!((0..1_000).all(|_| !sampler.sample(LogLevel::Trace, Sampling::Probability(0.0))));
+        assert!((0..1_000).all(|_| sampler.sample(LogLevel::Trace, Sampling::Probability(1.0))));
+    }
+}
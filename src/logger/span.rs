@@ -0,0 +1,157 @@
+use std::panic::Location;
+use std::time::Instant;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+/// Guard that times an operation, created with the [`log_span!`](crate::log_span) macro or [`TheLogger::span`]. A debug
+/// record is logged when it's created and another one when it's dropped, with the elapsed time. Nested spans, created
+/// with [`TheLoggerSpan::child`], show the path of their parent spans:
+/// ```text
+/// 2023-12-17 01:51:10.677106  [DEBUG]  @src/main.rs: 37    load_user started
+/// 2023-12-17 01:51:10.677219  [DEBUG]  @src/main.rs: 38    load_user > query_db started
+/// 2023-12-17 01:51:10.702689  [DEBUG]  @src/main.rs: 38    load_user > query_db finished in 25.470ms
+/// 2023-12-17 01:51:10.702701  [DEBUG]  @src/main.rs: 37    load_user finished in 25.595ms
+/// ```
+pub struct TheLoggerSpan<'a> {
+    logger: &'a TheLogger,
+    path: String,
+    module_path: &'static str,
+    location: &'static Location<'static>,
+    start: Instant
+}
+
+impl<'a> TheLoggerSpan<'a> {
+    #[doc(hidden)]
+    fn start(
+        logger: &'a TheLogger,
+        path: String,
+        module_path: &'static str,
+        location: &'static Location<'static>
+    ) -> Self {
+        logger.log_blocking(
+            LogLevel::Debug,
+            module_path,
+            (location.file(), location.line(), location.column()),
+            &format!("{} started", path)
+        );
+        Self {
+            logger,
+            path,
+            module_path,
+            location,
+            start: Instant::now()
+        }
+    }
+
+    /// ## Description
+    /// Starts a span nested in this one. Its records show the path of the parent spans, as in "load_user > query_db",
+    /// and the module path of the parent span
+    #[track_caller]
+    pub fn child(&self, name: &str) -> TheLoggerSpan<'a> {
+        TheLoggerSpan::start(self.logger, format!("{} > {}", self.path, name), self.module_path, Location::caller())
+    }
+}
+
+impl Drop for TheLoggerSpan<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        self.logger.log_blocking(
+            LogLevel::Debug,
+            self.module_path,
+            (self.location.file(), self.location.line(), self.location.column()),
+            &format!("{} finished in {:.3}ms", self.path, elapsed.as_secs_f64() * 1000.0)
+        );
+    }
+}
+
+impl TheLogger {
+    /// ## Description
+    /// Starts a span that logs a debug record now, and another one with the elapsed time when the returned guard is
+    /// dropped. The records are logged with the location where the span was started, but without its module path,
+    /// which a method can't capture: use the [`log_span!`](crate::log_span) macro to fill it in.
+    ///
+    /// The current span isn't tracked, so a span started with this method within another one isn't nested in it,
+    /// even in a function called from the outer span. Nest it explicitly with [`TheLoggerSpan::child`], passing the
+    /// parent span down where needed.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::TheLogger;
+    ///
+    /// async fn load_user(user_id: u32) {
+    ///     let logger: &TheLogger = TheLogger::instance();
+    ///     let span = logger.span("load_user");
+    ///     {
+    ///         let _query_span = span.child("query_db");
+    ///         //  Query the database...
+    ///     }
+    ///     //  Both spans log their elapsed time when dropped
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn span(&self, name: &str) -> TheLoggerSpan<'_> {
+        TheLoggerSpan::start(self, name.to_string(), "", Location::caller())
+    }
+
+    /// ## Description
+    /// Starts a span like [`TheLogger::span`], logging its records with the given module path. The
+    /// [`log_span!`](crate::log_span) macro fills in the module path of the call site.
+    #[track_caller]
+    pub fn span_in_module(&self, module_path: &'static str, name: &str) -> TheLoggerSpan<'_> {
+        TheLoggerSpan::start(self, name.to_string(), module_path, Location::caller())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::logger::logger_config::{LocationSource, TheLoggerConfig};
+    use super::*;
+
+    #[test]
+    fn spans_log_their_path_and_elapsed_time() {
+        let capture = TheLogger::capture();
+        let logger = TheLogger::instance();
+        let (span, span_line) = (logger.span("load_user"), line!());
+        {
+            let _query_span = span.child("query_db");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        drop(span);
+
+        let records = capture.records();
+        let messages: Vec<&str> = records.iter().map(|record| record.message.as_str()).collect();
+        assert!(records.iter().all(|record| record.level == LogLevel::Debug));
+        assert!(messages[0].ends_with("load_user started"));
+        assert!(messages[0].contains(&format!("span.rs: {}", span_line)));
+        assert!(messages[1].ends_with("load_user > query_db started"));
+        assert!(messages[3].contains(&format!("span.rs: {}", span_line)));
+
+        let elapsed_ms = |message: &str, path: &str| -> f64 {
+            let (_, elapsed) = message.split_once(&format!("{} finished in ", path)).unwrap();
+            elapsed.strip_suffix("ms").unwrap().parse().unwrap()
+        };
+        let query_elapsed = elapsed_ms(messages[2], "load_user > query_db");
+        assert!(query_elapsed >= 20.0);
+        assert!(elapsed_ms(messages[3], "load_user") >= query_elapsed);
+    }
+
+    #[test]
+    fn spans_started_with_the_macro_log_their_module_path() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_span_{}", std::process::id()));
+        let config = TheLoggerConfig::default()
+            .log_directory(&log_directory)
+            .location_source(LocationSource::ModulePath);
+        let logger = TheLogger::with_config(config).unwrap();
+        let capture = TheLogger::capture();
+        {
+            let span = crate::log_span!(logger, "load_user");
+            let _query_span = span.child("query_db");
+        }
+
+        let records = capture.records();
+        std::fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(records.len(), 4);
+        assert!(records.iter().all(|record| record.message.contains("@the_logger::logger::span::tests: ")));
+    }
+}
@@ -1,545 +1,2476 @@
-use std::fs;
-use lazy_static::lazy_static;
-use std::fs::File;
-use std::io::Write;
-use tokio::sync::RwLock;
-use crate::logger::logger_config::{LogLevel, TheLoggerConfig};
-
-lazy_static!(
-    /// Static reference that allows the user to access the logger from anywhere in the code.
-    static ref THE_LOGGER: TheLogger = TheLogger::new();
-);
-
-/// Main struct to instantiate when using TheLogger. Its inner RwLock allows only one usage of the file writer and
-/// configuration member at a time.
-pub struct TheLogger {
-    inner: RwLock<TheLoggerInner>
-}
-
-#[doc(hidden)]
-struct TheLoggerInner {
-    config: TheLoggerConfig,
-    file_writer: File
-}
-
-
-
-impl TheLogger {
-    #[doc(hidden)]
-    fn new() -> Self {
-        fs::create_dir_all("./logs/").unwrap();
-        Self {
-            inner: RwLock::new(TheLoggerInner {
-                config: TheLoggerConfig::default(),
-                file_writer: std::fs::OpenOptions::new()
-                                     .write(true)
-                                     .create(true)
-                                     .append(true)
-                                     .open(
-                                         format!("./logs/Log {}.log", chrono::Local::now().naive_local().format("%Y-%m-%d"))
-                                     ).unwrap()
-            })
-        }
-    }
-
-    /// ## Description
-    /// Returns the instance of the logger. This is the main way to access the logger, and it'll be configured by default.
-    ///
-    /// TheLogger has built-in support for the format! macro, so the user can use it to format the log message without
-    /// having to allocate an extra variable, as shown in the example below.
-    ///
-    /// Its setup can be changed later using the methods provided by this crate.
-    ///
-    /// ### Default settings:
-    /// - All date elements enabled
-    /// - All time elements enabled
-    /// - Log level enabled and set to VERBOSE
-    /// - Time format set to Local time
-    /// - File name and line number shown, but column number is hidden.
-    /// - File name, location, column information section is limited to a maximum default of 60 chars, configurable.
-    /// - Log content is limited to a maximum default of 300 chars, also configurable.
-    ///
-    /// ### Log Example
-    /// ```text
-    /// 2023-12-16 17:08:07.451851800  [VERBOSE]  This is a log example for the the_logger crate
-    /// ```
-    ///
-    /// ### Usage example
-    /// ````rust
-    /// use the_logger::{log_warning, TheLogger};
-    ///
-    /// async fn init_logger(thread_id: u8) {
-    ///     let logger: &TheLogger = TheLogger::instance();
-    ///     log_warning!(logger, "This is a warning emitted by thread {}", thread_id);
-    /// }
-    /// ````
-    pub fn instance() -> &'static Self {
-        &THE_LOGGER
-    }
-
-    /// ## Description
-    /// Executes the logging to the file according to the current configuration
-    pub async fn log_in_file(&self, (file, line, column): (&str, u32, u32), incoming_msg: &str) {
-        let mut msg = String::new();
-        let mut location_info = String::new();
-        let location_length;
-        let content_length;
-
-        {
-            let inner = self.inner.read().await;
-
-            //  Datetime formatting
-            let datetime = if inner.config.get_utc_config() {
-                chrono::Utc::now().naive_utc()
-            } else {
-                chrono::Local::now().naive_local()
-            };
-            let mut datetime_format = String::new();
-
-            //  Date formatting
-            let mut space_date_time = false;
-            let mut time_shown = false;
-            if !inner.config.get_years_config() {
-                datetime_format.push_str("%Y");
-                space_date_time = true;
-            }
-            if !inner.config.get_months_config() {
-                space_date_time = true;
-                datetime_format.push_str("-%m");
-            }
-            if !inner.config.get_days_config() {
-                space_date_time = true;
-                datetime_format.push_str("-%d");
-            }
-            if space_date_time {
-                datetime_format.push(' ');
-            }
-
-            //  Time formatting
-            if !inner.config.get_hours_config() {
-                datetime_format.push_str("%H");
-                time_shown = true;
-            }
-            if !inner.config.get_minutes_config() {
-                datetime_format.push_str(":%M");
-                time_shown = true;
-            }
-            if !inner.config.get_seconds_config() {
-                datetime_format.push_str(":%S");
-                time_shown = true;
-            }
-            match (inner.config.get_millisecs_config(), inner.config.get_microsecs_config()) {
-                (false, false) => {
-                    datetime_format.push_str(".%6f");
-                    time_shown = true;
-                },
-                (false, true) => {
-                    datetime_format.push_str(".%3f");
-                    time_shown = true;
-                },
-                (true, false) => {},
-                _ => {}
-            }
-            if space_date_time || time_shown {
-                datetime_format.push('\t');
-            }
-            msg.push_str(&datetime.format(&datetime_format).to_string());
-
-            //  Log level type config
-            if !inner.config.get_level_config() {
-                match inner.config.get_log_level() {
-                    LogLevel::Verbose => {
-                        msg.push_str("[VERBOSE]\t");
-                    },
-                    LogLevel::Information => {
-                        msg.push_str("[INFO]\t\t");
-                    },
-                    LogLevel::Error => {
-                        msg.push_str("[ERROR]\t\t");
-                    },
-                    LogLevel::Warning => {
-                        msg.push_str("[WARNING]\t");
-                    },
-                    LogLevel::Debug => {
-                        msg.push_str("[DEBUG]\t\t");
-                    },
-                    LogLevel::Trace => {
-                        msg.push_str("[TRACE]\t\t");
-                    },
-                    LogLevel::Critical => {
-                        msg.push_str("[CRITICAL]\t");
-                    }
-                }
-                //  Only insert tab if location data is not shown
-            } else if !inner.config.get_file_name_config() || !inner.config.get_file_line_config() || inner.config.get_file_column_config() {
-                msg.push('\t');
-            }
-
-            //  File location configuration
-            if !inner.config.get_file_name_config() {
-                location_info.push_str(format!("@{}", file).as_str());
-
-                if !inner.config.get_file_line_config() {
-                    location_info.push_str(format!(": {}", line).as_str());
-
-                    if inner.config.get_file_column_config() {
-                        location_info.push_str(format!("|{}", column).as_str());
-                    }
-                }
-            }
-
-            //  Location info and content lengths configuration
-            location_length = inner.config.get_location_length();
-            content_length = inner.config.get_log_content_length();
-        }
-
-        //  Close the message string and log it. Trim the message if it's longer than the configured lengths
-        if location_length < location_info.len() {
-            location_info = location_info.as_str()[0..location_length].to_string();
-            //  Append a tab to separate the location info and the content a little bit
-            location_info.push_str("\t\t");
-        }
-        if !location_info.is_empty() {
-            msg.push_str(format!("{:<location_length$}", location_info).as_str());
-        }
-        if content_length < incoming_msg.len() {
-            msg.push_str(incoming_msg[0..content_length].as_ref());
-        } else {
-            msg.push_str(incoming_msg);
-        }
-        self.inner.write().await.file_writer.write_all(format!("{}\n", msg).as_bytes()).unwrap();
-    }
-
-    /// ## Description
-    /// Allows the user to configure the logger using a single config call. The configuration must previously be created
-    /// by instantiating the TheLoggerConfig struct adn setting each desired field with its builder methods.
-    ///
-    /// Every change to the configuration is made through this method.
-    ///
-    /// ### Initial configuration example
-    /// ```rust
-    /// use the_logger::{TheLogger, TheLoggerConfig};
-    ///
-    /// async fn init_logger() {
-    ///     let logger_config = TheLoggerConfig::default()
-    ///         .hide_file_line()
-    ///         .hide_microsecs()
-    ///         .hide_level();
-    ///
-    ///     let logger: &TheLogger = TheLogger::instance().config(logger_config).await;
-    /// }
-    /// ```
-    ///
-    /// As a result, the user has configured the logger to hide the file line number, the microsecs and the log level,
-    ///
-    /// and leave the rest of the parameters with their default values.
-    ///
-    /// ### Modifying configuration example
-    /// If the user needs to modify the logger configuration for any particular reason, for example for a specific
-    ///
-    /// routine that doesn't need to show all the data or needs to show different data, this can be achieved in a similar
-    ///
-    /// way to the initial configuration:
-    ///
-    /// ```rust
-    /// use the_logger::{TheLogger, TheLoggerConfig};
-    ///
-    /// async fn config_logger() {
-    ///     let logger_config = TheLoggerConfig::default()
-    ///         .show_file_line()
-    ///         .show_microsecs()
-    ///         .show_level();
-    ///
-    ///     let logger: &TheLogger = TheLogger::instance().config(logger_config).await;
-    /// }
-    /// ```
-    ///
-    /// As opposed to the previous example, we're now reverting the changes made to the logger configuration, so that
-    ///
-    /// the file line number, microseconds stamp and log level are shown.
-    pub async fn config(&self, logger_config: TheLoggerConfig) -> &Self {
-        self.inner.write().await.config = logger_config;
-        self
-    }
-
-    //////////////////////
-    /* Log Type methods */
-    //////////////////////
-    /// ## Description
-    /// Configures the log level as verbose adding the [[VERBOSE]] tag
-    pub async fn verbose(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Verbose);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as informational adding the [[INFO]] tag
-    pub async fn info(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Information);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as error adding the [[ERROR]] tag
-    pub async fn error(&self) -> &Self{
-        self.inner.write().await.config.set_log_level(LogLevel::Error);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as warning adding the [[WARNING]] tag
-    pub async fn warning(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Warning);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as debug adding the [[DEBUG]] tag
-    pub async fn debug(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Debug);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as trace adding the [[TRACE]] tag
-    pub async fn trace(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Trace);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level as critical adding the [[CRITICAL]] tag
-    pub async fn critical(&self) -> &Self {
-        self.inner.write().await.config.set_log_level(LogLevel::Critical);
-        self
-    }
-
-    ///////////////////////////
-    /* Configuration methods */
-    ///////////////////////////
-    /* Hide methods */
-    //////////////////
-    /// ## Description
-    /// Configures the log date to hide the years. Default is to show them
-    pub async fn hide_years(&self) -> &Self {
-        self.inner.write().await.config.set_years_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log date to hide the months. Default is to show them
-    pub async fn hide_months(&self) -> &Self {
-        self.inner.write().await.config.set_months_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log date to hide the days. Default is to show them
-    pub async fn hide_days(&self) -> &Self {
-        self.inner.write().await.config.set_days_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to hide the hours. Default is to show them
-    pub async fn hide_hours(&self) -> &Self {
-        self.inner.write().await.config.set_hours_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to hide the minutes. Default is to show them
-    pub async fn hide_minutes(&self) -> &Self {
-        self.inner.write().await.config.set_minutes_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to hide the seconds. Default is to show them
-    pub async fn hide_seconds(&self) -> &Self {
-        self.inner.write().await.config.set_seconds_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to hide the milliseconds. Default is to show them
-    ///
-    /// ### Warning
-    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
-    ///
-    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
-    pub async fn hide_millisecs(&self) -> &Self {
-        self.inner.write().await.config.set_millisecs_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to hide the microseconds. Default is to show them
-    ///
-    /// ### Warning
-    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
-    ///
-    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
-    pub async fn hide_microsecs(&self) -> &Self {
-        self.inner.write().await.config.set_microsecs_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log timezone to UTC format. Default is Local time
-    pub async fn utc_time(&self) -> &Self {
-        self.inner.write().await.config.set_utc_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level to be hidden. Default is to show it
-    pub async fn hide_level(&self) -> &Self {
-        self.inner.write().await.config.set_level_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file name, line and column to be hidden. Default is to show them
-    pub async fn hide_file_name(&self) -> &Self {
-        self.inner.write().await.config.set_file_name_config(true);
-        self.inner.write().await.config.set_file_line_config(true);
-        self.inner.write().await.config.set_file_column_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file line and column to be hidden. Default is to show them
-    pub async fn hide_file_line(&self) -> &Self {
-        self.inner.write().await.config.set_file_line_config(true);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file column to be shown. Default is to hide it
-    pub async fn show_file_column(&self) -> &Self {
-        self.inner.write().await.config.set_file_column_config(true);
-        self
-    }
-
-    //////////////////
-    /* Show methods */
-    //////////////////
-    /// ## Description
-    /// Configures the log date to show the years. Default is to show them
-    pub async fn show_years(&self) -> &Self {
-        self.inner.write().await.config.set_years_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log date to show the months. Default is to show them
-    pub async fn show_months(&self) -> &Self {
-        self.inner.write().await.config.set_months_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log date to show the days. Default is to show them
-    pub async fn show_days(&self) -> &Self {
-        self.inner.write().await.config.set_days_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to show the hours. Default is to show them
-    pub async fn show_hours(&self) -> &Self {
-        self.inner.write().await.config.set_hours_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to show the minutes. Default is to show them
-    pub async fn show_minutes(&self) -> &Self {
-        self.inner.write().await.config.set_minutes_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to show the seconds. Default is to show them
-    pub async fn show_seconds(&self) -> &Self {
-        self.inner.write().await.config.set_seconds_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to show the milliseconds. Default is to show them
-    ///
-    /// ### Warning
-    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
-    ///
-    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
-    pub async fn show_millisecs(&self) -> &Self {
-        self.inner.write().await.config.set_millisecs_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log time to show the microseconds. Default is to show them
-    ///
-    /// ### Warning
-    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
-    ///
-    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
-    pub async fn show_microsecs(&self) -> &Self {
-        self.inner.write().await.config.set_microsecs_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log timezone to Local format. Default is Local time
-    pub async fn local_time(&self) -> &Self {
-        self.inner.write().await.config.set_utc_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log level to be shown. Default is to show it
-    pub async fn show_level(&self) -> &Self {
-        self.inner.write().await.config.set_level_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file name, line and column to be shown. Default is to show them
-    pub async fn show_file_name(&self) -> &Self {
-        self.inner.write().await.config.set_file_name_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file line and column to be shown. Default is to show them
-    pub async fn show_file_line(&self) -> &Self {
-        self.inner.write().await.config.set_file_line_config(false);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log file column to be shown. Default is to hide it
-    pub async fn hide_file_column(&self) -> &Self {
-        self.inner.write().await.config.set_file_column_config(false);
-        self
-    }
-
-    ///////////////////////////
-    /* Length configurations */
-    ///////////////////////////
-    /// ## Description
-    /// Configures the log file name, line and column location content's length. Default is 100 characters
-    pub async fn location_content_length(&self, length: usize) -> &Self {
-        self.inner.write().await.config.set_location_length(length);
-        self
-    }
-
-    /// ## Description
-    /// Configures the log message content's length. Default is 300 characters
-    pub async fn log_content_length(&self, length: usize) -> &Self {
-        self.inner.write().await.config.set_log_content_length(length);
-        self
-    }
-}
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, TryLockError, Weak};
+use std::thread::Thread;
+use std::time::{Duration, Instant};
+use crate::logger::capture::capture_record;
+use crate::logger::context::current_context;
+#[cfg(feature = "encryption")]
+use crate::logger::encryption::{EncryptedSegment, EncryptionKey, RecordTag};
+use crate::logger::error::{FailureStrategy, TheLoggerError};
+use crate::logger::fields::FieldValue;
+#[cfg(feature = "audit")]
+use crate::logger::hash_chain::HashChain;
+use crate::logger::rate_limit::{suppressed_summary, CallSite, RateLimitDecision, SuppressedRecords};
+#[cfg(feature = "redaction")]
+use crate::logger::redaction::{redact, RedactionRule};
+use crate::logger::sampling::Sampler;
+use crate::logger::logger_config::{
+    FlushPolicy, LocationSource, LocationTruncation, LogLevel, MultilineMode, Sampling, TheLoggerConfig
+};
+
+/// Static reference that allows the user to access the logger from anywhere in the code. It's set either explicitly
+/// by [`TheLogger::init`] or with the default configuration the first time [`TheLogger::instance`] is called.
+static THE_LOGGER: OnceLock<TheLogger> = OnceLock::new();
+
+/// Hostname of the machine, fetched only once the first time it's shown in a record.
+#[cfg(feature = "hostname")]
+static HOSTNAME: OnceLock<String> = OnceLock::new();
+
+/// Registry of the named logger instances, fetched by [`TheLogger::named`].
+static NAMED_LOGGERS: OnceLock<std::sync::RwLock<HashMap<String, &'static TheLogger>>> = OnceLock::new();
+
+thread_local! {
+    /// Addresses of the inner locks of the loggers held by the current thread. A record logged while the lock is held,
+    /// such as from a failure or redaction callback, is discarded instead of deadlocking
+    static HELD_LOCKS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Main struct to instantiate when using TheLogger. Its inner Mutex allows only one usage of the file writer and
+/// configuration member at a time. The lock is never held across an await point, so it's safe to use it both from
+/// async tasks and from synchronous code such as the log crate facade.
+pub struct TheLogger {
+    inner: Arc<Mutex<TheLoggerInner>>,
+    shut_down: AtomicBool,
+    in_flight: Mutex<usize>,
+    drained: Condvar,
+    sampler: Sampler
+}
+
+/// Guard that shuts down the logger it was created from when it's dropped. Keep it alive until the end of `main` to
+/// make sure every buffered record reaches the log file before the program exits.
+///
+/// ### Usage example
+/// ```rust
+/// use the_logger::{log_info, TheLogger};
+///
+/// async fn run() {
+///     let logger: &'static TheLogger = TheLogger::instance();
+///     let _guard = logger.shutdown_guard();
+///
+///     log_info!(logger, "This record is flushed when the guard goes out of scope");
+/// }
+/// ```
+pub struct TheLoggerGuard {
+    logger: &'static TheLogger
+}
+
+impl Drop for TheLogger {
+    fn drop(&mut self) {
+        //  Wakes the maintenance thread up so it finds out the logger is gone and ends
+        if let Some(thread) = self.lock().and_then(|mut inner| inner.maintenance.thread.take()) {
+            thread.unpark();
+        }
+    }
+}
+
+impl Drop for TheLoggerGuard {
+    fn drop(&mut self) {
+        //  Dropped from a callback running under the lock, draining would wait for the record it's writing forever
+        if self.logger.locked_by_current_thread() {
+            return;
+        }
+        self.logger.stop_and_drain();
+        let Some(mut inner) = self.logger.lock() else {
+            return;
+        };
+        if let Err(error) = inner.shutdown() {
+            inner.handle_failure(error, None);
+        }
+    }
+}
+
+#[doc(hidden)]
+struct TheLoggerInner {
+    config: TheLoggerConfig,
+    file_writer: Option<BufWriter<File>>,
+    /// Path of the open log file, which is named after the day it was opened on rather than the current one
+    #[cfg_attr(not(any(feature = "audit", feature = "encryption")), allow(dead_code))]
+    file_path: Option<PathBuf>,
+    records_since_flush: usize,
+    last_flush: Instant,
+    failing: bool,
+    duplicate: Option<PendingDuplicate>,
+    suppressed_call_sites: Vec<SuppressedCallSite>,
+    maintenance: Maintenance,
+    #[cfg(feature = "audit")]
+    hash_chain: Option<HashChain>,
+    #[cfg(feature = "encryption")]
+    encrypted_segment: Option<EncryptedSegment>
+}
+
+#[doc(hidden)]
+/// Guard of the logger's inner lock, which keeps track of the lock being held by the current thread until it's dropped
+struct TheLoggerInnerGuard<'a> {
+    inner: MutexGuard<'a, TheLoggerInner>,
+    logger: usize
+}
+
+impl<'a> TheLoggerInnerGuard<'a> {
+    #[doc(hidden)]
+    fn new(inner: MutexGuard<'a, TheLoggerInner>, lock: &Mutex<TheLoggerInner>) -> Self {
+        let logger = lock as *const Mutex<TheLoggerInner> as usize;
+        HELD_LOCKS.with(|held_locks| held_locks.borrow_mut().push(logger));
+        Self { inner, logger }
+    }
+}
+
+impl Deref for TheLoggerInnerGuard<'_> {
+    type Target = TheLoggerInner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for TheLoggerInnerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl Drop for TheLoggerInnerGuard<'_> {
+    fn drop(&mut self) {
+        let _ = HELD_LOCKS.try_with(|held_locks| {
+            let mut held_locks = held_locks.borrow_mut();
+            if let Some(index) = held_locks.iter().rposition(|logger| *logger == self.logger) {
+                held_locks.remove(index);
+            }
+        });
+    }
+}
+
+#[doc(hidden)]
+/// Record being logged, registered by [`TheLogger::start_record`] until it's written or discarded
+struct InFlightRecord<'a> {
+    logger: &'a TheLogger
+}
+
+impl Drop for InFlightRecord<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.logger.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *in_flight -= 1;
+        if *in_flight == 0 {
+            self.logger.drained.notify_all();
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Background thread that writes what the logger holds back once it's due, such as the buffered records of the
+/// [`FlushPolicy::EveryMillis`] policy. It's started the first time it's needed and ends when the logger is dropped
+struct Maintenance {
+    inner: Weak<Mutex<TheLoggerInner>>,
+    thread: Option<Thread>
+}
+
+#[doc(hidden)]
+/// Runs the maintenance of the logger until it's dropped, sleeping until the next pending work is due or until a
+/// record wakes it up
+fn run_maintenance(inner: Weak<Mutex<TheLoggerInner>>) {
+    loop {
+        let Some(lock) = inner.upgrade() else {
+            return;
+        };
+        let next_deadline = {
+            let guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            TheLoggerInnerGuard::new(guard, &lock).maintain(Instant::now())
+        };
+        //  Released while sleeping, so dropping the logger ends the thread
+        drop(lock);
+        match next_deadline {
+            Some(deadline) => std::thread::park_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => std::thread::park()
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Run of consecutive identical records being collapsed by the deduplication mode. The first record of the run is
+/// written as usual, and the rest are written as a single record when the run ends
+struct PendingDuplicate {
+    log_level: LogLevel,
+    key: String,
+    started: Instant,
+    last_record: String,
+    repeated: usize
+}
+
+#[doc(hidden)]
+/// Rate limited call site that suppressed records within its current window. The summary of the suppressed records is
+/// written before its next allowed record, or else when the window ends or the logger is flushed
+struct SuppressedCallSite {
+    call_site: &'static CallSite,
+    log_level: LogLevel,
+    module_path: &'static str,
+    location: (&'static str, u32, u32),
+    window: Duration
+}
+
+impl TheLoggerInner {
+    #[doc(hidden)]
+    /// Builds the record's line according to the current configuration
+    fn format_record(
+        &self,
+        log_level: LogLevel,
+        module_path: &str,
+        (file, line, column): (&str, u32, u32),
+        fields: &[(&str, FieldValue)],
+        incoming_msg: &str
+    ) -> String {
+        let mut msg = String::new();
+        let mut location_info = String::new();
+
+        //  Secrets are redacted before truncating the content, a partially cut off secret wouldn't match anymore
+        #[cfg(feature = "redaction")]
+        let incoming_msg = &*redact(self.config.get_redaction_rules(), incoming_msg);
+
+        //  Datetime formatting
+        let datetime = if self.config.get_utc_config() {
+            chrono::Utc::now().naive_utc()
+        } else {
+            chrono::Local::now().naive_local()
+        };
+        let mut datetime_format = String::new();
+
+        //  Date formatting
+        let mut space_date_time = false;
+        let mut time_shown = false;
+        if !self.config.get_years_config() {
+            datetime_format.push_str("%Y");
+            space_date_time = true;
+        }
+        if !self.config.get_months_config() {
+            space_date_time = true;
+            datetime_format.push_str("-%m");
+        }
+        if !self.config.get_days_config() {
+            space_date_time = true;
+            datetime_format.push_str("-%d");
+        }
+        if space_date_time {
+            datetime_format.push(' ');
+        }
+
+        //  Time formatting
+        if !self.config.get_hours_config() {
+            datetime_format.push_str("%H");
+            time_shown = true;
+        }
+        if !self.config.get_minutes_config() {
+            datetime_format.push_str(":%M");
+            time_shown = true;
+        }
+        if !self.config.get_seconds_config() {
+            datetime_format.push_str(":%S");
+            time_shown = true;
+        }
+        match (self.config.get_millisecs_config(), self.config.get_microsecs_config()) {
+            (false, false) => {
+                datetime_format.push_str(".%6f");
+                time_shown = true;
+            },
+            (false, true) => {
+                datetime_format.push_str(".%3f");
+                time_shown = true;
+            },
+            (true, false) => {},
+            _ => {}
+        }
+        if space_date_time || time_shown {
+            datetime_format.push('\t');
+        }
+        msg.push_str(&datetime.format(&datetime_format).to_string());
+
+        //  Log level type config
+        if !self.config.get_level_config() {
+            match log_level {
+                LogLevel::Verbose => {
+                    msg.push_str("[VERBOSE]\t");
+                },
+                LogLevel::Information => {
+                    msg.push_str("[INFO]\t\t");
+                },
+                LogLevel::Error => {
+                    msg.push_str("[ERROR]\t\t");
+                },
+                LogLevel::Warning => {
+                    msg.push_str("[WARNING]\t");
+                },
+                LogLevel::Debug => {
+                    msg.push_str("[DEBUG]\t\t");
+                },
+                LogLevel::Trace => {
+                    msg.push_str("[TRACE]\t\t");
+                },
+                LogLevel::Critical => {
+                    msg.push_str("[CRITICAL]\t");
+                }
+            }
+            //  Only insert tab if location data is not shown
+        } else if !self.config.get_file_name_config() || !self.config.get_file_line_config() || self.config.get_file_column_config() {
+            msg.push('\t');
+        }
+
+        //  Thread, task and process metadata columns
+        let mut metadata = Vec::new();
+        #[cfg(feature = "hostname")]
+        if self.config.get_hostname_config() {
+            metadata.push(HOSTNAME.get_or_init(|| gethostname::gethostname().to_string_lossy().into_owned()).clone());
+        }
+        if self.config.get_pid_config() {
+            metadata.push(format!("pid:{}", std::process::id()));
+        }
+        if self.config.get_thread_name_config() {
+            metadata.push(format!("thread:{}", std::thread::current().name().unwrap_or("unnamed")));
+        }
+        if self.config.get_thread_id_config() {
+            //  ThreadId only exposes its numeric value through its Debug implementation, as in "ThreadId(1)"
+            let thread_id = format!("{:?}", std::thread::current().id());
+            metadata.push(format!("tid:{}", thread_id.trim_start_matches("ThreadId(").trim_end_matches(')')));
+        }
+        if self.config.get_task_id_config() {
+            match tokio::task::try_id() {
+                Some(task_id) => metadata.push(format!("task:{}", task_id)),
+                None => metadata.push("task:-".to_string())
+            }
+        }
+        if !metadata.is_empty() {
+            msg.push_str(&metadata.join(" "));
+            msg.push('\t');
+        }
+
+        //  File location configuration
+        if !self.config.get_file_name_config() {
+            let file = match self.config.get_location_truncation() {
+                LocationTruncation::StripPrefix(prefix) => file.strip_prefix(prefix.as_str()).unwrap_or(file),
+                _ => file
+            };
+            match (self.config.get_location_source(), module_path.is_empty()) {
+                (LocationSource::ModulePath, false) => location_info.push_str(format!("@{}", module_path).as_str()),
+                (LocationSource::Both, false) => location_info.push_str(format!("@{} {}", module_path, file).as_str()),
+                _ => location_info.push_str(format!("@{}", file).as_str())
+            }
+
+            if !self.config.get_file_line_config() {
+                location_info.push_str(format!(": {}", line).as_str());
+
+                if self.config.get_file_column_config() {
+                    location_info.push_str(format!("|{}", column).as_str());
+                }
+            }
+        }
+
+        //  Location info and content lengths configuration
+        let location_length = self.config.get_location_length();
+        let content_length = self.config.get_log_content_length();
+
+        //  Close the message string and log it. Trim the message if it's longer than the configured lengths
+        if location_length < location_info.len() {
+            location_info = match self.config.get_location_truncation() {
+                LocationTruncation::KeepHead => truncate_at_char_boundary(&location_info, location_length).to_string(),
+                LocationTruncation::KeepTail | LocationTruncation::StripPrefix(_) => {
+                    keep_tail(&location_info, location_length)
+                },
+                LocationTruncation::ElideMiddle => elide_middle(&location_info, location_length)
+            };
+            //  Append a tab to separate the location info and the content a little bit
+            location_info.push_str("\t\t");
+        }
+        if !location_info.is_empty() {
+            msg.push_str(format!("{:<location_length$}", location_info).as_str());
+        }
+        let header_length = msg.len();
+        if content_length < incoming_msg.len() {
+            let content = truncate_at_char_boundary(incoming_msg, content_length);
+            msg.push_str(content);
+            if self.config.get_truncation_marker_config() {
+                msg.push_str(format!("…[truncated {} bytes]", incoming_msg.len() - content.len()).as_str());
+            }
+        } else {
+            msg.push_str(incoming_msg);
+        }
+
+        //  Structured fields, appended as k=v pairs after the content. Context fields go first
+        let mut fields_info = String::new();
+        if let Some(context) = current_context() {
+            for (key, value) in context.iter() {
+                fields_info.push_str(format!(" {}={}", key, value).as_str());
+            }
+        }
+        for (key, value) in fields {
+            fields_info.push_str(format!(" {}={}", key, value).as_str());
+        }
+        #[cfg(feature = "redaction")]
+        let fields_info = redact(self.config.get_redaction_rules(), &fields_info);
+        msg.push_str(&fields_info);
+
+        //  Backslashes are escaped first and even without line breaks, so an escaped line break can't be mistaken for
+        //  a backslash followed by an 'n' in the content
+        let escape = matches!(self.config.get_multiline_mode(), MultilineMode::Escape);
+        if escape && msg[header_length..].contains(['\\', '\r', '\n']) {
+            let (header, content) = msg.split_at(header_length);
+            let content = content.replace('\\', "\\\\").replace('\r', "\\r").replace('\n', "\\n");
+            return format!("{}{}", header, content);
+        }
+
+        //  Multi-line content handling, to keep one record per line or make the continuation lines recognizable
+        if msg[header_length..].contains('\n') {
+            let (header, content) = msg.split_at(header_length);
+            let continuation_prefix = match self.config.get_multiline_mode() {
+                MultilineMode::Raw | MultilineMode::Escape => return msg,
+                //  Keep the tabs so the continuation lines are aligned with the content
+                MultilineMode::Indent => header.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect(),
+                MultilineMode::RepeatHeader => header.to_string()
+            };
+
+            let mut lines = content.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line));
+            let mut multiline_msg = format!("{}{}", header, lines.next().unwrap_or_default());
+            for line in lines {
+                multiline_msg.push('\n');
+                multiline_msg.push_str(&continuation_prefix);
+                multiline_msg.push_str(line);
+            }
+            return multiline_msg;
+        }
+
+        msg
+    }
+
+    #[doc(hidden)]
+    /// Writes a single record, handling any failure with the configured strategy. With the deduplication mode
+    /// enabled, a record identical to the previous one is held back instead, see [`PendingDuplicate`]. While the
+    /// current thread is capturing, the record is collected in memory instead, see [`TheLogger::capture`]
+    fn log_record(&mut self, log_level: LogLevel, msg: &str) {
+        if capture_record(log_level, msg) {
+            return;
+        }
+        if let Some(timeout) = self.config.get_deduplication_timeout() {
+            let key = self.duplicate_key(msg);
+            if let Some(duplicate) = self.duplicate.as_mut() {
+                if duplicate.key == key && duplicate.started.elapsed() < timeout {
+                    duplicate.last_record = msg.to_string();
+                    duplicate.repeated += 1;
+                    //  The first held back record sets when the run ends
+                    if duplicate.repeated == 1 {
+                        self.wake_maintenance();
+                    }
+                    return;
+                }
+            }
+
+            let key = key.to_string();
+            self.log_pending_duplicate();
+            self.duplicate = Some(PendingDuplicate {
+                log_level,
+                key,
+                started: Instant::now(),
+                last_record: String::new(),
+                repeated: 0
+            });
+        }
+
+        match self.write_record(log_level, msg) {
+            Ok(()) => self.failing = false,
+            Err(error) => self.handle_failure(error, Some(msg))
+        }
+    }
+
+    #[doc(hidden)]
+    /// Returns the part of the record that identifies it as a duplicate: its level, location and content, without the
+    /// date and time that precede them
+    fn duplicate_key<'a>(&self, msg: &'a str) -> &'a str {
+        let datetime_shown = !self.config.get_years_config()
+            || !self.config.get_months_config()
+            || !self.config.get_days_config()
+            || !self.config.get_hours_config()
+            || !self.config.get_minutes_config()
+            || !self.config.get_seconds_config()
+            || !self.config.get_millisecs_config();
+        match (datetime_shown, msg.split_once('\t')) {
+            (true, Some((_, key))) => key,
+            _ => msg
+        }
+    }
+
+    #[doc(hidden)]
+    /// Ends the current run of identical records, writing the held back ones as a single record with a
+    /// "[repeated N times]" suffix
+    fn write_pending_duplicate(&mut self) -> Result<(), TheLoggerError> {
+        match self.duplicate.take() {
+            Some(duplicate) if duplicate.repeated > 0 => {
+                let times = if duplicate.repeated == 1 { "time" } else { "times" };
+                let msg = format!("{} [repeated {} {}]", duplicate.last_record, duplicate.repeated, times);
+                self.write_record(duplicate.log_level, &msg)
+            },
+            _ => Ok(())
+        }
+    }
+
+    #[doc(hidden)]
+    /// Ends the current run of identical records, handling any failure with the configured strategy
+    fn log_pending_duplicate(&mut self) {
+        if let Err(error) = self.write_pending_duplicate() {
+            self.handle_failure(error, None);
+        }
+    }
+
+    #[doc(hidden)]
+    /// Writes a single record into the buffered writer and flushes it if the configured policy requires it
+    fn write_record(&mut self, log_level: LogLevel, msg: &str) -> Result<(), TheLoggerError> {
+        #[cfg(feature = "audit")]
+        let chained_record = self.chain_record(msg)?;
+        #[cfg(feature = "audit")]
+        let msg = chained_record.as_ref().map_or(msg, |(record, _)| record.as_str());
+
+        #[cfg(feature = "encryption")]
+        let (record, tag) = match self.encrypt_record(msg)? {
+            Some((frame, tag)) => (frame, Some(tag)),
+            None => (format!("{}\n", msg).into_bytes(), None)
+        };
+        #[cfg(not(feature = "encryption"))]
+        let record = format!("{}\n", msg).into_bytes();
+
+        self.file_writer()?.write_all(&record).map_err(TheLoggerError::Write)?;
+        self.records_since_flush += 1;
+        #[cfg(feature = "audit")]
+        if let (Some(hash_chain), Some((_, hash))) = (self.hash_chain.as_mut(), chained_record) {
+            hash_chain.advance(hash);
+        }
+        #[cfg(feature = "encryption")]
+        if let (Some(segment), Some(tag)) = (self.encrypted_segment.as_mut(), tag) {
+            segment.advance(tag);
+        }
+
+        let flush_needed = match self.config.get_flush_policy() {
+            FlushPolicy::EveryRecord => true,
+            FlushPolicy::EveryRecords(records) => self.records_since_flush >= records,
+            FlushPolicy::EveryMillis(millis) => self.last_flush.elapsed() >= Duration::from_millis(millis),
+            FlushPolicy::OnErrorOrCritical => matches!(log_level, LogLevel::Error | LogLevel::Critical)
+        };
+        if flush_needed {
+            self.flush()?;
+        } else if self.records_since_flush == 1 {
+            //  The first buffered record sets when the next flush is due
+            self.wake_maintenance();
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// Wakes the maintenance thread up to find out when its pending work is due, starting it the first time
+    fn wake_maintenance(&mut self) {
+        if let Some(thread) = self.maintenance.thread.as_ref() {
+            thread.unpark();
+            return;
+        }
+        //  Without a thread, what's held back is still written by the next record, flush or shutdown
+        let inner = self.maintenance.inner.clone();
+        if inner.strong_count() > 0 {
+            let thread = std::thread::Builder::new()
+                .name("the_logger maintenance".to_string())
+                .spawn(move || run_maintenance(inner));
+            self.maintenance.thread = thread.ok().map(|thread| thread.thread().clone());
+        }
+    }
+
+    #[doc(hidden)]
+    /// Writes what's due at the given instant, and returns when the next pending work will be due, if there's any
+    fn maintain(&mut self, now: Instant) -> Option<Instant> {
+        let summary_deadline = self.log_suppressed_summaries(Some(now));
+
+        //  The run is written before flushing, so the flush includes it
+        let duplicate_deadline = self.duplicate.as_ref().filter(|duplicate| duplicate.repeated > 0).map(|duplicate| {
+            self.config.get_deduplication_timeout().map_or(now, |timeout| duplicate.started + timeout)
+        });
+        let duplicate_deadline = match duplicate_deadline {
+            Some(deadline) if deadline <= now => {
+                self.log_pending_duplicate();
+                None
+            },
+            deadline => deadline
+        };
+
+        let flush_interval = match self.config.get_flush_policy() {
+            FlushPolicy::EveryMillis(millis) if self.records_since_flush > 0 => Some(Duration::from_millis(millis)),
+            _ => None
+        };
+        let flush_deadline = match flush_interval.map(|interval| (self.last_flush + interval, interval)) {
+            Some((deadline, interval)) if deadline <= now => match self.flush() {
+                Ok(()) => None,
+                Err(error) => {
+                    //  Retried after another interval instead of right away
+                    self.last_flush = now;
+                    self.handle_failure(error, None);
+                    Some(now + interval)
+                }
+            },
+            deadline => deadline.map(|(deadline, _)| deadline)
+        };
+
+        [summary_deadline, duplicate_deadline, flush_deadline].into_iter().flatten().min()
+    }
+
+    #[doc(hidden)]
+    /// Logs the summaries of the suppressed records whose window ends by the given instant, or of every one without an
+    /// instant. Returns when the next pending window ends
+    fn log_suppressed_summaries(&mut self, until: Option<Instant>) -> Option<Instant> {
+        let mut next_deadline: Option<Instant> = None;
+        let mut summaries = Vec::new();
+        self.suppressed_call_sites.retain(|call_site| {
+            match call_site.call_site.take_suppressed(call_site.window, until) {
+                SuppressedRecords::None => false,
+                SuppressedRecords::Due(suppressed) => {
+                    summaries.push((call_site.log_level, call_site.module_path, call_site.location, suppressed));
+                    false
+                },
+                SuppressedRecords::Until(window_end) => {
+                    next_deadline = Some(next_deadline.map_or(window_end, |next| next.min(window_end)));
+                    true
+                }
+            }
+        });
+
+        for (log_level, module_path, location, suppressed) in summaries {
+            let msg = self.format_record(log_level, module_path, location, &[], &suppressed_summary(suppressed));
+            self.log_record(log_level, &msg);
+        }
+        next_deadline
+    }
+
+    #[cfg(feature = "audit")]
+    #[doc(hidden)]
+    /// Returns the record with its chained hash and the hash itself if the hash chain is enabled. The chain is resumed
+    /// from the log file the first time it's needed after the file is opened
+    fn chain_record(&mut self, msg: &str) -> Result<Option<(String, String)>, TheLoggerError> {
+        if !self.config.get_hash_chain_config() {
+            return Ok(None);
+        }
+        //  Encrypted files aren't hash chained, the encryption chains every record to the previous one instead
+        #[cfg(feature = "encryption")]
+        if self.config.get_encryption_key().is_some() {
+            return Ok(None);
+        }
+        if self.hash_chain.is_none() {
+            //  The records written before the chain was enabled must be in the file to resume it
+            self.file_writer()?.flush().map_err(TheLoggerError::Flush)?;
+            if let (Some(log_file), Some(file_writer)) = (self.file_path.as_deref(), self.file_writer.as_mut()) {
+                let hash_chain = HashChain::resume(log_file, file_writer.get_mut()).map_err(TheLoggerError::Write)?;
+                self.hash_chain = Some(hash_chain);
+            }
+        }
+        Ok(self.hash_chain.as_ref().map(|hash_chain| hash_chain.chain(msg)))
+    }
+
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    /// Returns the frame of the encrypted record and its tag if the encryption is enabled. A new segment is started at
+    /// the end of the log file the first time it's needed after the file is opened
+    fn encrypt_record(&mut self, msg: &str) -> Result<Option<(Vec<u8>, RecordTag)>, TheLoggerError> {
+        let Some(key) = self.config.get_encryption_key().cloned() else {
+            return Ok(None);
+        };
+        if self.encrypted_segment.is_none() {
+            //  The segment is started right on the file, so nothing may be left in the buffer before it
+            self.file_writer()?.flush().map_err(TheLoggerError::Flush)?;
+            if let (Some(log_file), Some(file_writer)) = (self.file_path.as_deref(), self.file_writer.as_mut()) {
+                let segment = EncryptedSegment::start(&key, log_file, file_writer.get_mut());
+                self.encrypted_segment = Some(segment.map_err(TheLoggerError::Write)?);
+            }
+        }
+        self.encrypted_segment
+            .as_mut()
+            .map(|segment| segment.encrypt(msg))
+            .transpose()
+            .map_err(TheLoggerError::Write)
+    }
+
+    #[doc(hidden)]
+    /// Returns the file writer, trying to open the log file again if it couldn't be opened before
+    fn file_writer(&mut self) -> Result<&mut BufWriter<File>, TheLoggerError> {
+        let file_writer = match self.file_writer.take() {
+            Some(file_writer) => file_writer,
+            None => {
+                let (file_writer, file_path) = TheLogger::open_log_file(&self.config)?;
+                self.file_path = Some(file_path);
+                file_writer
+            }
+        };
+        Ok(self.file_writer.insert(file_writer))
+    }
+
+    #[doc(hidden)]
+    fn flush(&mut self) -> Result<(), TheLoggerError> {
+        if let Some(file_writer) = self.file_writer.as_mut() {
+            file_writer.flush().map_err(TheLoggerError::Flush)?;
+        }
+        self.records_since_flush = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// Flushes the pending records and syncs the file to disk if configured to do so
+    fn shutdown(&mut self) -> Result<(), TheLoggerError> {
+        self.log_suppressed_summaries(None);
+        self.write_pending_duplicate()?;
+        self.flush()?;
+        if let (true, Some(file_writer)) = (self.config.get_fsync_on_shutdown_config(), self.file_writer.as_ref()) {
+            file_writer.get_ref().sync_all().map_err(TheLoggerError::Sync)?;
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// Replaces the configuration. If the log file's directory, name or encryption changed, the current file is
+    /// flushed and closed so the next record opens the new one
+    fn set_config(&mut self, config: TheLoggerConfig) {
+        let file_changed = config.get_log_directory() != self.config.get_log_directory()
+            || config.get_log_file_prefix() != self.config.get_log_file_prefix();
+        #[cfg(feature = "encryption")]
+        let file_changed = file_changed || config.get_encryption_key() != self.config.get_encryption_key();
+        //  Closed before the configuration is replaced, so a pending duplicate is written as the current file expects
+        if file_changed {
+            self.close_file();
+        }
+        self.config = config;
+        if self.config.get_deduplication_timeout().is_none() {
+            self.log_pending_duplicate();
+        }
+        if self.records_since_flush > 0 {
+            self.wake_maintenance();
+        }
+    }
+
+    #[doc(hidden)]
+    fn close_file(&mut self) {
+        self.log_pending_duplicate();
+        if let Err(error) = self.flush() {
+            self.handle_failure(error, None);
+        }
+        self.file_writer = None;
+        self.file_path = None;
+        #[cfg(feature = "audit")]
+        {
+            self.hash_chain = None;
+        }
+        #[cfg(feature = "encryption")]
+        {
+            self.encrypted_segment = None;
+        }
+    }
+
+    #[doc(hidden)]
+    /// Applies the configured failure strategy to an error that can't be returned to the caller
+    fn handle_failure(&mut self, error: TheLoggerError, record: Option<&str>) {
+        match self.config.get_failure_strategy() {
+            FailureStrategy::Ignore => {},
+            FailureStrategy::Stderr => {
+                //  Report the error only once to avoid flooding stderr while the logger can't recover
+                if !self.failing {
+                    eprintln!("the_logger: {}", error);
+                }
+                if let Some(record) = record {
+                    eprintln!("{}", record);
+                }
+            },
+            FailureStrategy::Callback(callback) => callback(&error, record)
+        }
+        self.failing = true;
+    }
+}
+
+#[doc(hidden)]
+/// Returns the longest prefix of the text that fits in the maximum length in bytes without splitting a multi-byte
+/// character
+fn truncate_at_char_boundary(text: &str, max_length: usize) -> &str {
+    if max_length >= text.len() {
+        return text;
+    }
+
+    let mut end = max_length;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[doc(hidden)]
+/// Ellipsis that marks where the location was cut off
+const ELLIPSIS: &str = "…";
+
+#[doc(hidden)]
+/// Returns the end of the text that fits in the maximum length in bytes, preceded by an ellipsis
+fn keep_tail(text: &str, max_length: usize) -> String {
+    if max_length < ELLIPSIS.len() {
+        return truncate_at_char_boundary(text, max_length).to_string();
+    }
+
+    let mut start = text.len() - (max_length - ELLIPSIS.len());
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("{}{}", ELLIPSIS, &text[start..])
+}
+
+#[doc(hidden)]
+/// Returns the first directory of the path and the end of the text that fits in the maximum length in bytes, with an
+/// ellipsis in between. The end starts at a path separator when possible, as in "@src/…/handler.rs: 42"
+fn elide_middle(text: &str, max_length: usize) -> String {
+    if max_length < ELLIPSIS.len() {
+        return truncate_at_char_boundary(text, max_length).to_string();
+    }
+
+    //  The first directory takes at most half of the available length
+    let available = max_length - ELLIPSIS.len();
+    let head = match text.find(['/', '\\']) {
+        Some(separator) if separator < available / 2 => &text[..=separator],
+        _ => truncate_at_char_boundary(text, available / 2)
+    };
+
+    let mut start = text.len() - (available - head.len());
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let mut tail = &text[start..];
+    if let Some(separator) = tail.find(['/', '\\']) {
+        tail = &tail[separator..];
+    }
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+impl TheLogger {
+    #[doc(hidden)]
+    /// Creates the logger with an already opened file writer and the path of its file, or without one to open it when
+    /// the first record is logged
+    fn new(config: TheLoggerConfig, log_file: Option<(BufWriter<File>, PathBuf)>) -> Self {
+        let (file_writer, file_path) = log_file.unzip();
+        Self {
+            inner: Arc::new_cyclic(|inner| Mutex::new(TheLoggerInner {
+                config,
+                file_writer,
+                file_path,
+                records_since_flush: 0,
+                last_flush: Instant::now(),
+                failing: false,
+                duplicate: None,
+                suppressed_call_sites: Vec::new(),
+                maintenance: Maintenance { inner: inner.clone(), thread: None },
+                #[cfg(feature = "audit")]
+                hash_chain: None,
+                #[cfg(feature = "encryption")]
+                encrypted_segment: None
+            })),
+            shut_down: AtomicBool::new(false),
+            in_flight: Mutex::new(0),
+            drained: Condvar::new(),
+            sampler: Sampler::new()
+        }
+    }
+
+    #[doc(hidden)]
+    /// Creates the configured logs directory if needed and opens today's log file in append mode, with the configured
+    /// permissions if they're created. Returns the file writer along with the path of the file
+    fn open_log_file(config: &TheLoggerConfig) -> Result<(BufWriter<File>, PathBuf), TheLoggerError> {
+        let log_directory = config.get_log_directory();
+        let mut directory_builder = fs::DirBuilder::new();
+        directory_builder.recursive(true);
+        #[cfg(unix)]
+        if let Some(mode) = config.get_directory_mode() {
+            std::os::unix::fs::DirBuilderExt::mode(&mut directory_builder, mode);
+        }
+        directory_builder.create(log_directory).map_err(TheLoggerError::CreateDirectory)?;
+
+        let mut open_options = fs::OpenOptions::new();
+        open_options.create(true).append(true);
+        #[cfg(unix)]
+        if let Some(mode) = config.get_file_mode() {
+            std::os::unix::fs::OpenOptionsExt::mode(&mut open_options, mode);
+        }
+        let file_path = Self::log_file_path(config);
+        let file = open_options.open(&file_path).map_err(TheLoggerError::OpenFile)?;
+
+        Ok((BufWriter::new(file), file_path))
+    }
+
+    #[doc(hidden)]
+    /// Returns the path of today's log file, named with the configured prefix followed by the date, and with the
+    /// ".log.enc" extension if the records are encrypted
+    fn log_file_path(config: &TheLoggerConfig) -> PathBuf {
+        #[cfg(feature = "encryption")]
+        let extension = if config.get_encryption_key().is_some() { "log.enc" } else { "log" };
+        #[cfg(not(feature = "encryption"))]
+        let extension = "log";
+        config.get_log_directory().join(format!(
+            "{} {}.{}",
+            config.get_log_file_prefix(),
+            chrono::Local::now().naive_local().format("%Y-%m-%d"),
+            extension
+        ))
+    }
+
+    /// ## Description
+    /// Initializes the logger's instance with the provided configuration. Unlike [`TheLogger::instance`], the log
+    /// file is opened right away, so any error creating the logs directory or the file is returned to the caller.
+    ///
+    /// It can be called only once, and only before the instance is used. Otherwise,
+    /// [`TheLoggerError::AlreadyInitialized`] is returned and the existing instance is left untouched. Every call to
+    /// [`TheLogger::instance`] afterwards returns the initialized logger.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, TheLogger, TheLoggerConfig, TheLoggerError};
+    ///
+    /// async fn init_logger() -> Result<(), TheLoggerError> {
+    ///     let logger_config = TheLoggerConfig::default()
+    ///         .log_directory("./service_logs/")
+    ///         .hide_file_column();
+    ///
+    ///     let logger: &TheLogger = TheLogger::init(logger_config)?;
+    ///     log_info!(logger, "Logger initialized");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn init(config: TheLoggerConfig) -> Result<&'static Self, TheLoggerError> {
+        if THE_LOGGER.get().is_some() {
+            return Err(TheLoggerError::AlreadyInitialized);
+        }
+
+        let log_file = Self::open_log_file(&config)?;
+        THE_LOGGER.set(Self::new(config, Some(log_file))).map_err(|_| TheLoggerError::AlreadyInitialized)?;
+        Ok(Self::instance())
+    }
+
+    /// ## Description
+    /// Creates a logger independent of the static instance, with its own configuration and log file. The log file is
+    /// opened right away, returning any error to the caller.
+    ///
+    /// Give each instance a different [`TheLoggerConfig::log_file_prefix`] or [`TheLoggerConfig::log_directory`],
+    /// otherwise they'll write into the same file. To access the instance from anywhere in the code, register it with
+    /// [`TheLogger::register`] instead.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, TheLogger, TheLoggerConfig, TheLoggerError};
+    ///
+    /// async fn process_batch() -> Result<(), TheLoggerError> {
+    ///     let batch_logger = TheLogger::with_config(TheLoggerConfig::default().log_file_prefix("Batch"))?;
+    ///     log_info!(batch_logger, "Batch started");
+    ///     batch_logger.shutdown().await
+    /// }
+    /// ```
+    pub fn with_config(config: TheLoggerConfig) -> Result<Self, TheLoggerError> {
+        let log_file = Self::open_log_file(&config)?;
+        Ok(Self::new(config, Some(log_file)))
+    }
+
+    /// ## Description
+    /// Creates a logger independent of the static instance, as described in [`TheLogger::with_config`], and registers
+    /// it with the given name so it can be fetched from anywhere in the code with [`TheLogger::named`].
+    ///
+    /// Returns [`TheLoggerError::NameAlreadyRegistered`] if another logger was registered with the same name. Registered
+    /// loggers live until the program exits, just like the static instance.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, TheLogger, TheLoggerConfig, TheLoggerError};
+    ///
+    /// fn init_loggers() -> Result<(), TheLoggerError> {
+    ///     TheLogger::register("audit", TheLoggerConfig::default().log_file_prefix("Audit"))?;
+    ///     TheLogger::register("access", TheLoggerConfig::default().log_file_prefix("Access"))?;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn delete_user(user_id: u32) {
+    ///     if let Some(audit_logger) = TheLogger::named("audit") {
+    ///         log_info!(audit_logger, "User {} deleted", user_id);
+    ///     }
+    /// }
+    /// ```
+    pub fn register(name: &str, config: TheLoggerConfig) -> Result<&'static Self, TheLoggerError> {
+        let mut named_loggers = NAMED_LOGGERS.get_or_init(Default::default)
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if named_loggers.contains_key(name) {
+            return Err(TheLoggerError::NameAlreadyRegistered(name.to_string()));
+        }
+
+        let logger: &'static Self = Box::leak(Box::new(Self::with_config(config)?));
+        named_loggers.insert(name.to_string(), logger);
+        Ok(logger)
+    }
+
+    /// ## Description
+    /// Returns the logger registered with the given name by [`TheLogger::register`], if any
+    pub fn named(name: &str) -> Option<&'static Self> {
+        NAMED_LOGGERS.get()?
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .copied()
+    }
+
+    #[cfg(any(feature = "log_facade", feature = "tracing_layer"))]
+    #[doc(hidden)]
+    pub(super) fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::SeqCst)
+    }
+
+    #[cfg(feature = "log_facade")]
+    #[doc(hidden)]
+    /// Flushes the buffered records without an async context, handling any failure with the configured strategy
+    pub(super) fn flush_blocking(&self) {
+        let Some(mut inner) = self.lock() else {
+            return;
+        };
+        inner.log_suppressed_summaries(None);
+        inner.log_pending_duplicate();
+        if let Err(error) = inner.flush() {
+            inner.handle_failure(error, None);
+        }
+    }
+
+    #[doc(hidden)]
+    /// Logs and flushes a record without an async context, giving up if another thread holds the lock until the
+    /// timeout. If the current thread holds it, as when the panic hook runs for a panic in a callback running under the
+    /// lock, the record is skipped right away, since the lock wouldn't be released before the timeout anyway
+    pub(super) fn log_and_flush_with_timeout(
+        &self,
+        log_level: LogLevel,
+        location: (&str, u32, u32),
+        incoming_msg: &str,
+        timeout: Duration
+    ) {
+        //  The panicking thread could be running a callback under the lock, which would never be released
+        if self.locked_by_current_thread() {
+            return;
+        }
+        let Some(_record) = self.start_record() else {
+            return;
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(mut inner) = self.try_lock() {
+                let msg = inner.format_record(log_level, "", location, &[], incoming_msg);
+                inner.log_record(log_level, &msg);
+                if let Err(error) = inner.flush() {
+                    inner.handle_failure(error, None);
+                }
+                return;
+            }
+            if Instant::now() >= deadline {
+                return;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    #[doc(hidden)]
+    /// Acquires the inner lock. It's only held while a record is formatted and written or the configuration changes,
+    /// never across an await point, so blocking on it is safe even from within a tokio runtime. A panic while it was
+    /// held, such as in a user callback, doesn't leave the logger unusable. Returns None if the current thread already
+    /// holds it, as when a callback running under the lock uses the logger, since waiting for it would never end
+    fn lock(&self) -> Option<TheLoggerInnerGuard<'_>> {
+        if self.locked_by_current_thread() {
+            return None;
+        }
+        let inner = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Some(TheLoggerInnerGuard::new(inner, &self.inner))
+    }
+
+    #[doc(hidden)]
+    /// Changes the configuration under the inner lock. The change is skipped when made from a callback running under
+    /// the lock, see [`TheLogger::lock`]
+    fn update_config(&self, update: impl FnOnce(&mut TheLoggerConfig)) {
+        if let Some(mut inner) = self.lock() {
+            update(&mut inner.config);
+        }
+    }
+
+    #[doc(hidden)]
+    /// Acquires the inner lock if it's not held by anyone else, see [`TheLogger::lock`]
+    fn try_lock(&self) -> Option<TheLoggerInnerGuard<'_>> {
+        let inner = match self.inner.try_lock() {
+            Ok(inner) => inner,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return None
+        };
+        Some(TheLoggerInnerGuard::new(inner, &self.inner))
+    }
+
+    #[doc(hidden)]
+    /// Registers a record that's about to be logged, so the shutdown waits for it to be written. Returns None if the
+    /// logger was already shut down, so the record must be discarded
+    fn start_record(&self) -> Option<InFlightRecord<'_>> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        //  Checked under the same lock the shutdown sets it with, so every record either is waited for or discarded
+        if self.shut_down.load(Ordering::SeqCst) {
+            return None;
+        }
+        *in_flight += 1;
+        Some(InFlightRecord { logger: self })
+    }
+
+    #[doc(hidden)]
+    /// Stops accepting new records and waits until the ones already being logged are written
+    fn stop_and_drain(&self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.shut_down.store(true, Ordering::SeqCst);
+        while *in_flight > 0 {
+            in_flight = self.drained.wait(in_flight).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    #[doc(hidden)]
+    /// Checks whether the current thread holds the inner lock, as when a callback running under the lock logs a record
+    fn locked_by_current_thread(&self) -> bool {
+        let logger = &*self.inner as *const Mutex<TheLoggerInner> as usize;
+        HELD_LOCKS.try_with(|held_locks| held_locks.borrow().contains(&logger)).unwrap_or(false)
+    }
+
+    /// ## Description
+    /// Returns the instance of the logger. This is the main way to access the logger, and it'll be configured by default.
+    ///
+    /// TheLogger has built-in support for the format! macro, so the user can use it to format the log message without
+    /// having to allocate an extra variable, as shown in the example below.
+    ///
+    /// Its setup can be changed later using the methods provided by this crate.
+    ///
+    /// ### Default settings:
+    /// - All date elements enabled
+    /// - All time elements enabled
+    /// - Log level enabled and set to VERBOSE
+    /// - Time format set to Local time
+    /// - File name and line number shown, but column number is hidden.
+    /// - File name, location, column information section is limited to a maximum default of 60 chars, configurable.
+    /// - Log content is limited to a maximum default of 300 chars, also configurable.
+    ///
+    /// ### Log Example
+    /// ```text
+    /// 2023-12-16 17:08:07.451851800  [VERBOSE]  This is a log example for the the_logger crate
+    /// ```
+    ///
+    /// ### Usage example
+    /// ````rust
+    /// use the_logger::{log_warning, TheLogger};
+    ///
+    /// async fn init_logger(thread_id: u8) {
+    ///     let logger: &TheLogger = TheLogger::instance();
+    ///     log_warning!(logger, "This is a warning emitted by thread {}", thread_id);
+    /// }
+    /// ````
+    pub fn instance() -> &'static Self {
+        THE_LOGGER.get_or_init(|| {
+            //  The file is opened with the first record, so no directory is created if the records are captured or the
+            //  configuration changes before logging, and any error is handled from there
+            Self::new(TheLoggerConfig::default(), None)
+        })
+    }
+
+    /// ## Description
+    /// Executes the logging to the file according to the current configuration, with the configured log level
+    pub async fn log_in_file(&self, location: (&str, u32, u32), incoming_msg: &str) {
+        let Some(log_level) = self.lock().map(|inner| inner.config.get_log_level()) else {
+            return;
+        };
+        self.log_in_file_with_fields(log_level, "", location, &[], incoming_msg).await;
+    }
+
+    /// ## Description
+    /// Executes the logging to the file according to the current configuration, with the given log level, the module
+    /// path of the caller and the structured fields appended to the log content as k=v pairs. The log macros call this
+    /// method with the level of the macro, the `module_path!()` of the call site, and the fields provided with the
+    /// `key = value; message` syntax, see [`FieldValue`]. Unlike the level setters such as [`TheLogger::info`], the
+    /// level isn't stored in the shared configuration, so records logged concurrently never get each other's level.
+    pub async fn log_in_file_with_fields(
+        &self,
+        log_level: LogLevel,
+        module_path: &str,
+        location: (&str, u32, u32),
+        fields: &[(&str, FieldValue)],
+        incoming_msg: &str
+    ) {
+        let Some(_record) = self.start_record() else {
+            return;
+        };
+        let Some(mut inner) = self.lock() else {
+            return;
+        };
+        let msg = inner.format_record(log_level, module_path, location, fields, incoming_msg);
+        inner.log_record(log_level, &msg);
+    }
+
+    #[doc(hidden)]
+    /// Checks a record of a log macro call site against the sampling of its log level, and then against its rate limit:
+    /// the window of its `every = <window>;` clause, or else the default window of the log level. When the previous
+    /// window suppressed any record, a summary is logged before the allowed one, unless it was already logged when the
+    /// window ended or the logger was flushed. Returns whether the record must be logged, so the macros skip formatting
+    /// sampled-out and suppressed records
+    pub async fn record_allowed(
+        &self,
+        log_level: LogLevel,
+        call_site: &'static CallSite,
+        window: Option<Duration>,
+        module_path: &'static str,
+        location: (&'static str, u32, u32)
+    ) -> bool {
+        let Some((sampling, default_window)) =
+            self.lock().map(|inner| (inner.config.get_sampling(log_level), inner.config.get_rate_limit(log_level)))
+        else {
+            return false;
+        };
+        if let Some(sampling) = sampling {
+            if !self.sampler.sample(log_level, sampling) {
+                return false;
+            }
+        }
+        let Some(window) = window.or(default_window) else {
+            return true;
+        };
+
+        match call_site.check(window) {
+            RateLimitDecision::Suppress { suppressed: 1 } => {
+                //  The first suppressed record makes the summary pending, in case the call site is never hit again
+                if let (Some(_record), Some(mut inner)) = (self.start_record(), self.lock()) {
+                    let suppressed_call_sites = &inner.suppressed_call_sites;
+                    if !suppressed_call_sites.iter().any(|pending| std::ptr::eq(pending.call_site, call_site)) {
+                        inner.suppressed_call_sites.push(SuppressedCallSite {
+                            call_site,
+                            log_level,
+                            module_path,
+                            location,
+                            window
+                        });
+                        inner.wake_maintenance();
+                    }
+                }
+                false
+            },
+            RateLimitDecision::Suppress { .. } => false,
+            RateLimitDecision::Log { suppressed: 0 } => true,
+            RateLimitDecision::Log { suppressed } => {
+                let summary = suppressed_summary(suppressed);
+                self.log_in_file_with_fields(log_level, module_path, location, &[], &summary).await;
+                true
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    /// Executes the logging without an async context, using the provided log level instead of the configured one.
+    /// Used where awaiting isn't possible, such as the log crate facade or the drop of a span
+    pub(super) fn log_blocking(
+        &self,
+        log_level: LogLevel,
+        module_path: &str,
+        location: (&str, u32, u32),
+        incoming_msg: &str
+    ) {
+        let Some(_record) = self.start_record() else {
+            return;
+        };
+        let Some(mut inner) = self.lock() else {
+            return;
+        };
+        if let Some(sampling) = inner.config.get_sampling(log_level) {
+            if !self.sampler.sample(log_level, sampling) {
+                return;
+            }
+        }
+        let msg = inner.format_record(log_level, module_path, location, &[], incoming_msg);
+        inner.log_record(log_level, &msg);
+    }
+
+    /// ## Description
+    /// Writes every buffered record into the log file, regardless of the configured flush policy. A run of identical
+    /// records held back by the deduplication mode is written too.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, FlushPolicy, TheLogger, TheLoggerConfig};
+    ///
+    /// async fn log_batch() {
+    ///     let logger: &TheLogger = TheLogger::instance()
+    ///         .config(TheLoggerConfig::default().flush_policy(FlushPolicy::EveryRecords(100))).await;
+    ///
+    ///     for record in 0..250 {
+    ///         log_info!(logger, "Processed record {}", record);
+    ///     }
+    ///     //  The last 50 records are still buffered at this point
+    ///     if let Err(error) = logger.flush().await {
+    ///         eprintln!("Couldn't flush the logs: {}", error);
+    ///     }
+    /// }
+    /// ```
+    pub async fn flush(&self) -> Result<(), TheLoggerError> {
+        let mut inner = self.lock().ok_or(TheLoggerError::Reentrant)?;
+        inner.log_suppressed_summaries(None);
+        inner.write_pending_duplicate()?;
+        inner.flush()
+    }
+
+    /// ## Description
+    /// Shuts down the logger: new records are discarded from this point on, the records already being written are
+    /// drained, and the buffered writer is flushed. If enabled in the configuration, the log file is also synced to
+    /// disk.
+    ///
+    /// Since the logger's static instance is never dropped, this is the only point where every record is guaranteed
+    /// to have reached the log file. See [`TheLogger::shutdown_guard`] to do this automatically at the end of `main`.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::{log_info, TheLogger, TheLoggerConfig};
+    ///
+    /// async fn run() {
+    ///     let logger: &TheLogger = TheLogger::instance()
+    ///         .config(TheLoggerConfig::default().enable_fsync_on_shutdown()).await;
+    ///
+    ///     log_info!(logger, "Last record before exiting");
+    ///     if let Err(error) = logger.shutdown().await {
+    ///         eprintln!("Couldn't shut down the logger: {}", error);
+    ///     }
+    /// }
+    /// ```
+    pub async fn shutdown(&self) -> Result<(), TheLoggerError> {
+        //  Checked before draining, which would otherwise wait for the record the callback runs under forever
+        if self.locked_by_current_thread() {
+            return Err(TheLoggerError::Reentrant);
+        }
+        self.stop_and_drain();
+        self.lock().ok_or(TheLoggerError::Reentrant)?.shutdown()
+    }
+
+    /// ## Description
+    /// Returns a guard that shuts down the logger when it's dropped, as described in [`TheLogger::shutdown`]. Since
+    /// dropping can't be awaited, it works both in and out of an async context. Errors are handled with the
+    /// configured failure strategy, since they can't be returned from a drop.
+    pub fn shutdown_guard(&'static self) -> TheLoggerGuard {
+        TheLoggerGuard { logger: self }
+    }
+
+    /// ## Description
+    /// Allows the user to configure the logger using a single config call. The configuration must previously be created
+    /// by instantiating the TheLoggerConfig struct adn setting each desired field with its builder methods.
+    ///
+    /// Every change to the configuration is made through this method.
+    ///
+    /// ### Initial configuration example
+    /// ```rust
+    /// use the_logger::{TheLogger, TheLoggerConfig};
+    ///
+    /// async fn init_logger() {
+    ///     let logger_config = TheLoggerConfig::default()
+    ///         .hide_file_line()
+    ///         .hide_microsecs()
+    ///         .hide_level();
+    ///
+    ///     let logger: &TheLogger = TheLogger::instance().config(logger_config).await;
+    /// }
+    /// ```
+    ///
+    /// As a result, the user has configured the logger to hide the file line number, the microsecs and the log level,
+    ///
+    /// and leave the rest of the parameters with their default values.
+    ///
+    /// ### Modifying configuration example
+    /// If the user needs to modify the logger configuration for any particular reason, for example for a specific
+    ///
+    /// routine that doesn't need to show all the data or needs to show different data, this can be achieved in a similar
+    ///
+    /// way to the initial configuration:
+    ///
+    /// ```rust
+    /// use the_logger::{TheLogger, TheLoggerConfig};
+    ///
+    /// async fn config_logger() {
+    ///     let logger_config = TheLoggerConfig::default()
+    ///         .show_file_line()
+    ///         .show_microsecs()
+    ///         .show_level();
+    ///
+    ///     let logger: &TheLogger = TheLogger::instance().config(logger_config).await;
+    /// }
+    /// ```
+    ///
+    /// As opposed to the previous example, we're now reverting the changes made to the logger configuration, so that
+    ///
+    /// the file line number, microseconds stamp and log level are shown.
+    pub async fn config(&self, logger_config: TheLoggerConfig) -> &Self {
+        if let Some(mut inner) = self.lock() {
+            inner.set_config(logger_config);
+        }
+        self
+    }
+
+    //////////////////////
+    /* Log Type methods */
+    //////////////////////
+    /// ## Description
+    /// Configures the log level as verbose adding the [[VERBOSE]] tag
+    pub async fn verbose(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Verbose));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as informational adding the [[INFO]] tag
+    pub async fn info(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Information));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as error adding the [[ERROR]] tag
+    pub async fn error(&self) -> &Self{
+        self.update_config(|config| config.set_log_level(LogLevel::Error));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as warning adding the [[WARNING]] tag
+    pub async fn warning(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Warning));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as debug adding the [[DEBUG]] tag
+    pub async fn debug(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Debug));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as trace adding the [[TRACE]] tag
+    pub async fn trace(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Trace));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level as critical adding the [[CRITICAL]] tag
+    pub async fn critical(&self) -> &Self {
+        self.update_config(|config| config.set_log_level(LogLevel::Critical));
+        self
+    }
+
+    ///////////////////////////
+    /* Configuration methods */
+    ///////////////////////////
+    /* Hide methods */
+    //////////////////
+    /// ## Description
+    /// Configures the log date to hide the years. Default is to show them
+    pub async fn hide_years(&self) -> &Self {
+        self.update_config(|config| config.set_years_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log date to hide the months. Default is to show them
+    pub async fn hide_months(&self) -> &Self {
+        self.update_config(|config| config.set_months_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log date to hide the days. Default is to show them
+    pub async fn hide_days(&self) -> &Self {
+        self.update_config(|config| config.set_days_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to hide the hours. Default is to show them
+    pub async fn hide_hours(&self) -> &Self {
+        self.update_config(|config| config.set_hours_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to hide the minutes. Default is to show them
+    pub async fn hide_minutes(&self) -> &Self {
+        self.update_config(|config| config.set_minutes_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to hide the seconds. Default is to show them
+    pub async fn hide_seconds(&self) -> &Self {
+        self.update_config(|config| config.set_seconds_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to hide the milliseconds. Default is to show them
+    ///
+    /// ### Warning
+    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
+    ///
+    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
+    pub async fn hide_millisecs(&self) -> &Self {
+        self.update_config(|config| config.set_millisecs_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to hide the microseconds. Default is to show them
+    ///
+    /// ### Warning
+    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
+    ///
+    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
+    pub async fn hide_microsecs(&self) -> &Self {
+        self.update_config(|config| config.set_microsecs_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log timezone to UTC format. Default is Local time
+    pub async fn utc_time(&self) -> &Self {
+        self.update_config(|config| config.set_utc_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level to be hidden. Default is to show it
+    pub async fn hide_level(&self) -> &Self {
+        self.update_config(|config| config.set_level_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file name, line and column to be hidden. Default is to show them
+    pub async fn hide_file_name(&self) -> &Self {
+        self.update_config(|config| config.set_file_name_config(true));
+        self.update_config(|config| config.set_file_line_config(true));
+        self.update_config(|config| config.set_file_column_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file line and column to be hidden. Default is to show them
+    pub async fn hide_file_line(&self) -> &Self {
+        self.update_config(|config| config.set_file_line_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file column to be shown. Default is to hide it
+    pub async fn show_file_column(&self) -> &Self {
+        self.update_config(|config| config.set_file_column_config(true));
+        self
+    }
+
+    //////////////////
+    /* Show methods */
+    //////////////////
+    /// ## Description
+    /// Configures the log date to show the years. Default is to show them
+    pub async fn show_years(&self) -> &Self {
+        self.update_config(|config| config.set_years_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log date to show the months. Default is to show them
+    pub async fn show_months(&self) -> &Self {
+        self.update_config(|config| config.set_months_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log date to show the days. Default is to show them
+    pub async fn show_days(&self) -> &Self {
+        self.update_config(|config| config.set_days_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to show the hours. Default is to show them
+    pub async fn show_hours(&self) -> &Self {
+        self.update_config(|config| config.set_hours_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to show the minutes. Default is to show them
+    pub async fn show_minutes(&self) -> &Self {
+        self.update_config(|config| config.set_minutes_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to show the seconds. Default is to show them
+    pub async fn show_seconds(&self) -> &Self {
+        self.update_config(|config| config.set_seconds_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to show the milliseconds. Default is to show them
+    ///
+    /// ### Warning
+    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
+    ///
+    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
+    pub async fn show_millisecs(&self) -> &Self {
+        self.update_config(|config| config.set_millisecs_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log time to show the microseconds. Default is to show them
+    ///
+    /// ### Warning
+    /// Hiding the milliseconds and showing the microseconds would cause an unexpected time tracking in the logs,
+    ///
+    /// therefore, in this specific case, both milliseconds and microseconds will be hidden
+    pub async fn show_microsecs(&self) -> &Self {
+        self.update_config(|config| config.set_microsecs_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log timezone to Local format. Default is Local time
+    pub async fn local_time(&self) -> &Self {
+        self.update_config(|config| config.set_utc_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log level to be shown. Default is to show it
+    pub async fn show_level(&self) -> &Self {
+        self.update_config(|config| config.set_level_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file name, line and column to be shown. Default is to show them
+    pub async fn show_file_name(&self) -> &Self {
+        self.update_config(|config| config.set_file_name_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file line and column to be shown. Default is to show them
+    pub async fn show_file_line(&self) -> &Self {
+        self.update_config(|config| config.set_file_line_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log file column to be shown. Default is to hide it
+    pub async fn hide_file_column(&self) -> &Self {
+        self.update_config(|config| config.set_file_column_config(false));
+        self
+    }
+
+    ///////////////////////////////
+    /* Metadata column methods */
+    ///////////////////////////////
+    /// ## Description
+    /// Configures the log to show the name of the thread that logged the record. Default is to hide it
+    pub async fn show_thread_name(&self) -> &Self {
+        self.update_config(|config| config.set_thread_name_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the name of the thread that logged the record. Default is to hide it
+    pub async fn hide_thread_name(&self) -> &Self {
+        self.update_config(|config| config.set_thread_name_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the thread that logged the record. Default is to hide it
+    pub async fn show_thread_id(&self) -> &Self {
+        self.update_config(|config| config.set_thread_id_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the thread that logged the record. Default is to hide it
+    pub async fn hide_thread_id(&self) -> &Self {
+        self.update_config(|config| config.set_thread_id_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the tokio task that logged the record. Default is to hide it
+    pub async fn show_task_id(&self) -> &Self {
+        self.update_config(|config| config.set_task_id_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the tokio task that logged the record. Default is to hide it
+    pub async fn hide_task_id(&self) -> &Self {
+        self.update_config(|config| config.set_task_id_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the id of the process. Default is to hide it
+    pub async fn show_pid(&self) -> &Self {
+        self.update_config(|config| config.set_pid_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the id of the process. Default is to hide it
+    pub async fn hide_pid(&self) -> &Self {
+        self.update_config(|config| config.set_pid_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to show the hostname of the machine, with the `hostname` feature (disabled by default).
+    /// Default is to hide it
+    #[cfg(feature = "hostname")]
+    pub async fn show_hostname(&self) -> &Self {
+        self.update_config(|config| config.set_hostname_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to hide the hostname of the machine. Default is to hide it
+    #[cfg(feature = "hostname")]
+    pub async fn hide_hostname(&self) -> &Self {
+        self.update_config(|config| config.set_hostname_config(false));
+        self
+    }
+
+    ///////////////////////////
+    /* Length configurations */
+    ///////////////////////////
+    /// ## Description
+    /// Configures the log file name, line and column location content's length. Default is 100 characters
+    pub async fn location_content_length(&self, length: usize) -> &Self {
+        self.update_config(|config| config.set_location_length(length));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log message content's length. Default is 300 characters
+    pub async fn log_content_length(&self, length: usize) -> &Self {
+        self.update_config(|config| config.set_log_content_length(length));
+        self
+    }
+
+    /// ## Description
+    /// Configures how messages containing line breaks are written. Default is to write them as they are
+    pub async fn multiline_mode(&self, mode: MultilineMode) -> &Self {
+        self.update_config(|config| config.set_multiline_mode(mode));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to append a marker with the amount of bytes cut off when the content is truncated, as in
+    /// "…[truncated 1234 bytes]". Default is to hide it
+    pub async fn show_truncation_marker(&self) -> &Self {
+        self.update_config(|config| config.set_truncation_marker_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the log to truncate the content without a marker. Default is to hide it
+    pub async fn hide_truncation_marker(&self) -> &Self {
+        self.update_config(|config| config.set_truncation_marker_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures whether the location shows the file path, the module path or both. Default is the file path
+    pub async fn location_source(&self, source: LocationSource) -> &Self {
+        self.update_config(|config| config.set_location_source(source));
+        self
+    }
+
+    /// ## Description
+    /// Configures how the location is truncated when it's longer than the location content's length. Default is to
+    /// keep its start
+    pub async fn location_truncation(&self, truncation: LocationTruncation) -> &Self {
+        self.update_config(|config| config.set_location_truncation(truncation));
+        self
+    }
+
+    ///////////////////////////
+    /* Output configurations */
+    ///////////////////////////
+    /// ## Description
+    /// Configures the directory where the log files are created. The current file is flushed and closed, and the next
+    /// record opens a file in the new directory. Default is ./logs/
+    pub async fn log_directory(&self, path: impl Into<PathBuf>) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        let path = path.into();
+        if path.as_path() != inner.config.get_log_directory() {
+            inner.config.set_log_directory(path);
+            inner.close_file();
+        }
+        self
+    }
+
+    /// ## Description
+    /// Configures the name of the log files, which will be followed by the date. The current file is flushed and
+    /// closed, and the next record opens the file with the new name. Default is "Log"
+    pub async fn log_file_prefix(&self, prefix: impl Into<String>) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        let prefix = prefix.into();
+        if prefix != inner.config.get_log_file_prefix() {
+            inner.config.set_log_file_prefix(prefix);
+            inner.close_file();
+        }
+        self
+    }
+
+    /// ## Description
+    /// Configures when the buffered file writer is flushed. Default is to flush after every record
+    pub async fn flush_policy(&self, policy: FlushPolicy) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        inner.config.set_flush_policy(policy);
+        if inner.records_since_flush > 0 {
+            inner.wake_maintenance();
+        }
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to sync the log file to disk after the final flush when it's shut down. Default is to
+    /// leave the sync to the operating system
+    pub async fn enable_fsync_on_shutdown(&self) -> &Self {
+        self.update_config(|config| config.set_fsync_on_shutdown_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to leave the log file sync to the operating system when it's shut down. This is the
+    /// default
+    pub async fn disable_fsync_on_shutdown(&self) -> &Self {
+        self.update_config(|config| config.set_fsync_on_shutdown_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures how the logger handles a failure to write into the log file, such as a full disk or a read-only
+    /// directory. Default is to fall back to stderr
+    pub async fn on_failure(&self, strategy: FailureStrategy) -> &Self {
+        self.update_config(|config| config.set_failure_strategy(strategy));
+        self
+    }
+
+    /// ## Description
+    /// Configures the Unix permissions of the log files created from now on, such as 0o640. The process' umask still
+    /// applies. Default is the system's default permissions
+    pub async fn file_mode(&self, mode: u32) -> &Self {
+        self.update_config(|config| config.set_file_mode(Some(mode)));
+        self
+    }
+
+    /// ## Description
+    /// Configures the Unix permissions of the logs directories created from now on, such as 0o750. The process' umask
+    /// still applies. Default is the system's default permissions
+    pub async fn directory_mode(&self, mode: u32) -> &Self {
+        self.update_config(|config| config.set_directory_mode(Some(mode)));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to chain every record to the previous one with its hash, making the log files
+    /// tamper-evident. Encrypted files are chained by the encryption instead. See
+    /// [`TheLoggerConfig::enable_hash_chain`] for details. Default is to write the records without hashes
+    #[cfg(feature = "audit")]
+    pub async fn enable_hash_chain(&self) -> &Self {
+        self.update_config(|config| config.set_hash_chain_config(true));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write the records without hashes. This is the default
+    #[cfg(feature = "audit")]
+    pub async fn disable_hash_chain(&self) -> &Self {
+        self.update_config(|config| config.set_hash_chain_config(false));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to encrypt the records at rest with the key. The current file is flushed and closed, and
+    /// the next record opens the "<prefix> <date>.log.enc" file. See [`TheLoggerConfig::enable_encryption`] for
+    /// details. Default is to write the records as plain text
+    #[cfg(feature = "encryption")]
+    pub async fn enable_encryption(&self, key: EncryptionKey) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        if inner.config.get_encryption_key() != Some(&key) {
+            inner.close_file();
+            inner.config.set_encryption_key(Some(key));
+        }
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write the records as plain text. The current file is flushed and closed, and the next
+    /// record opens the "<prefix> <date>.log" file. This is the default
+    #[cfg(feature = "encryption")]
+    pub async fn disable_encryption(&self) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        if inner.config.get_encryption_key().is_some() {
+            inner.close_file();
+            inner.config.set_encryption_key(None);
+        }
+        self
+    }
+
+    ///////////////////////////
+    /* Filter configurations */
+    ///////////////////////////
+    /// ## Description
+    /// Configures the default rate limit of the log level: every log macro call site writes at most one record of the
+    /// level per window, and summarizes the suppressed ones when the window ends, or when the logger is flushed or shut
+    /// down. Default is no rate limit
+    pub async fn rate_limit(&self, level: LogLevel, window: Duration) -> &Self {
+        self.update_config(|config| config.set_rate_limit(level, Some(window)));
+        self
+    }
+
+    /// ## Description
+    /// Removes the default rate limit of the log level. This is the default
+    pub async fn no_rate_limit(&self, level: LogLevel) -> &Self {
+        self.update_config(|config| config.set_rate_limit(level, None));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to collapse consecutive identical records, with the same level, location and content,
+    /// into the first one and a single "[repeated N times]" record. The run ends when a different record arrives or
+    /// the timeout passes, and it's also written when the logger is flushed. Default is to write every record
+    pub async fn deduplicate(&self, timeout: Duration) -> &Self {
+        self.update_config(|config| config.set_deduplication_timeout(Some(timeout)));
+        self
+    }
+
+    /// ## Description
+    /// Configures the logger to write every record, including identical consecutive ones. This is the default. A run of
+    /// identical records already held back is written right away
+    pub async fn no_deduplication(&self) -> &Self {
+        let Some(mut inner) = self.lock() else {
+            return self;
+        };
+        inner.config.set_deduplication_timeout(None);
+        inner.log_pending_duplicate();
+        self
+    }
+
+    /// ## Description
+    /// Configures the sampling of the log level, keeping only 1 in N records or a random fraction of them. Default is
+    /// to keep every record
+    pub async fn sample(&self, level: LogLevel, sampling: Sampling) -> &Self {
+        self.update_config(|config| config.set_sampling(level, Some(sampling)));
+        self
+    }
+
+    /// ## Description
+    /// Removes the sampling of the log level, keeping every record. This is the default
+    pub async fn no_sampling(&self, level: LogLevel) -> &Self {
+        self.update_config(|config| config.set_sampling(level, None));
+        self
+    }
+
+    /// ## Description
+    /// Adds a rule to redact secrets and personal data from the log content and structured fields before they're
+    /// written, replacing each match with "***". Default is to write every record as it is
+    #[cfg(feature = "redaction")]
+    pub async fn redact(&self, rule: RedactionRule) -> &Self {
+        self.update_config(|config| config.add_redaction_rules(vec![rule]));
+        self
+    }
+
+    /// ## Description
+    /// Adds the built-in redaction rules: bearer tokens, passwords in URLs, credit card numbers and emails
+    #[cfg(feature = "redaction")]
+    pub async fn redact_builtin_patterns(&self) -> &Self {
+        self.update_config(|config| config.add_redaction_rules(RedactionRule::builtin()));
+        self
+    }
+
+    /// ## Description
+    /// Removes every redaction rule, writing the records as they are. This is the default
+    #[cfg(feature = "redaction")]
+    pub async fn no_redaction(&self) -> &Self {
+        self.update_config(|config| config.set_redaction_rules(Vec::new()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Polls the future once without a runtime. The logger's futures never wait, so they're ready right away
+    fn poll_once<F: std::future::Future>(future: F) -> std::task::Poll<F::Output> {
+        fn noop_raw_waker() -> std::task::RawWaker {
+            fn clone(_: *const ()) -> std::task::RawWaker {
+                noop_raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: std::task::RawWakerVTable = std::task::RawWakerVTable::new(clone, noop, noop, noop);
+            std::task::RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+
+        //  SAFETY: the waker's functions ignore the null data pointer
+        let waker = unsafe { std::task::Waker::from_raw(noop_raw_waker()) };
+        let mut future = std::pin::pin!(future);
+        future.as_mut().poll(&mut std::task::Context::from_waker(&waker))
+    }
+
+    fn inner_with_config(config: TheLoggerConfig) -> TheLoggerInner {
+        TheLoggerInner {
+            config: config.hide_years().hide_months().hide_days().hide_hours().hide_minutes().hide_seconds()
+                .hide_millisecs().hide_level().hide_file_name(),
+            file_writer: None,
+            file_path: None,
+            records_since_flush: 0,
+            last_flush: Instant::now(),
+            failing: false,
+            duplicate: None,
+            suppressed_call_sites: Vec::new(),
+            maintenance: Maintenance { inner: Weak::new(), thread: None },
+            #[cfg(feature = "audit")]
+            hash_chain: None,
+            #[cfg(feature = "encryption")]
+            encrypted_segment: None
+        }
+    }
+
+    #[test]
+    fn truncation_respects_char_boundaries() {
+        assert_eq!(truncate_at_char_boundary("año", 2), "a");
+        assert_eq!(truncate_at_char_boundary("año", 3), "añ");
+        assert_eq!(truncate_at_char_boundary("🦀🦀", 5), "🦀");
+        assert_eq!(truncate_at_char_boundary("🦀🦀", 3), "");
+        assert_eq!(truncate_at_char_boundary("ascii", 10), "ascii");
+    }
+
+    #[test]
+    fn non_ascii_content_is_truncated_without_panicking() {
+        //  The cut lands in the middle of the 'é'
+        let inner = inner_with_config(TheLoggerConfig::default().log_content_length(3));
+        let record = inner.format_record(LogLevel::Verbose, "", ("src/main.rs", 1, 1), &[], "Qué pasó? 🦀");
+
+        assert_eq!(record, "Qu");
+    }
+
+    #[test]
+    fn truncation_marker_counts_the_bytes_cut_off() {
+        let inner = inner_with_config(TheLoggerConfig::default().log_content_length(5).show_truncation_marker());
+        let record = inner.format_record(LogLevel::Verbose, "", ("src/main.rs", 1, 1), &[], "🦀🦀 crab");
+
+        assert_eq!(record, "🦀…[truncated 9 bytes]");
+    }
+
+    #[test]
+    fn non_ascii_location_is_truncated_without_panicking() {
+        //  The cut lands in the middle of the 'ñ'
+        let mut inner = inner_with_config(TheLoggerConfig::default().location_content_length(2));
+        inner.config.set_file_name_config(false);
+        let record = inner.format_record(LogLevel::Verbose, "", ("ñandú.rs", 1, 1), &[], "content");
+
+        assert_eq!(record, "\t@\t\tcontent");
+    }
+
+    #[test]
+    fn location_truncation_strategies_keep_the_file_name() {
+        let location = "@src/handlers/user/handler.rs: 42";
+
+        assert_eq!(keep_tail(location, 18), "…/handler.rs: 42");
+        assert_eq!(elide_middle(location, 24), "@src/…/handler.rs: 42");
+        assert!(elide_middle(location, 24).len() <= 24);
+        assert!(keep_tail("@ñandú/ñandú.rs: 1", 9).len() <= 9);
+    }
+
+    #[test]
+    fn location_prefix_is_stripped() {
+        let mut inner = inner_with_config(
+            TheLoggerConfig::default().location_truncation(LocationTruncation::StripPrefix("/home/dev/ws/".to_string()))
+        );
+        inner.config.set_file_name_config(false);
+        let record = inner.format_record(LogLevel::Verbose, "", ("/home/dev/ws/src/main.rs", 1, 1), &[], "content");
+
+        assert!(record.starts_with("\t@src/main.rs"));
+    }
+
+    #[test]
+    fn multiline_content_is_handled_by_the_configured_mode() {
+        let mut inner = inner_with_config(TheLoggerConfig::default());
+        inner.config.set_level_config(false);
+        let content = "panicked:\r\n  at main.rs\n  at lib.rs";
+
+        inner.config.set_multiline_mode(MultilineMode::Escape);
+        assert_eq!(
+            inner.format_record(LogLevel::Error, "", ("", 0, 0), &[], content),
+            "[ERROR]\t\tpanicked:\\r\\n  at main.rs\\n  at lib.rs"
+        );
+        //  A literal backslash followed by an 'n' stays distinguishable from an escaped line break
+        assert_eq!(
+            inner.format_record(LogLevel::Error, "", ("", 0, 0), &[], "C:\\new\nfolder"),
+            "[ERROR]\t\tC:\\\\new\\nfolder"
+        );
+        assert_eq!(inner.format_record(LogLevel::Error, "", ("", 0, 0), &[], "C:\\new"), "[ERROR]\t\tC:\\\\new");
+
+        inner.config.set_multiline_mode(MultilineMode::Indent);
+        assert_eq!(
+            inner.format_record(LogLevel::Error, "", ("", 0, 0), &[], content),
+            "[ERROR]\t\tpanicked:\n       \t\t  at main.rs\n       \t\t  at lib.rs"
+        );
+
+        inner.config.set_multiline_mode(MultilineMode::RepeatHeader);
+        assert_eq!(
+            inner.format_record(LogLevel::Error, "", ("", 0, 0), &[], content),
+            "[ERROR]\t\tpanicked:\n[ERROR]\t\t  at main.rs\n[ERROR]\t\t  at lib.rs"
+        );
+    }
+
+    #[cfg(feature = "redaction")]
+    #[test]
+    fn content_and_fields_are_redacted() {
+        let inner = inner_with_config(TheLoggerConfig::default().redact_builtin_patterns());
+        let fields = [("contact", FieldValue::from("jane@example.com"))];
+        let record = inner.format_record(LogLevel::Verbose, "", ("", 0, 0), &fields, "token Bearer abc.def sent");
+
+        assert_eq!(record, "token Bearer *** sent contact=***");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn log_files_are_created_with_the_configured_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let log_directory = std::env::temp_dir().join(format!("the_logger_modes_{}", std::process::id()));
+        let config = TheLoggerConfig::default().log_directory(log_directory.join("nested")).file_mode(0o600)
+            .directory_mode(0o700);
+        drop(TheLogger::open_log_file(&config).unwrap());
+
+        let mode = |path: &std::path::Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let log_file = fs::read_dir(log_directory.join("nested")).unwrap().next().unwrap().unwrap().path();
+        assert_eq!(mode(&log_directory), 0o700);
+        assert_eq!(mode(&log_directory.join("nested")), 0o700);
+        assert_eq!(mode(&log_file), 0o600);
+        fs::remove_dir_all(&log_directory).unwrap();
+    }
+
+    #[test]
+    fn identical_consecutive_records_are_collapsed() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_dedup_{}", std::process::id()));
+        let mut inner = inner_with_config(
+            TheLoggerConfig::default().log_directory(&log_directory).deduplicate(Duration::from_secs(60))
+        );
+
+        for msg in ["connection lost", "connection lost", "connection lost", "reconnected"] {
+            inner.log_record(LogLevel::Warning, msg);
+        }
+        inner.shutdown().unwrap();
+
+        let log_file = fs::read_dir(&log_directory).unwrap().next().unwrap().unwrap().path();
+        let records = fs::read_to_string(log_file).unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(records, "connection lost\nconnection lost [repeated 2 times]\nreconnected\n");
+    }
+
+    #[test]
+    fn runs_of_identical_records_are_written_when_the_timeout_passes() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_dedup_timeout_{}", std::process::id()));
+        let config = TheLoggerConfig::default().log_directory(&log_directory).deduplicate(Duration::from_millis(200));
+        let logger = TheLogger::new(inner_with_config(config).config, None);
+
+        //  No different record arrives and the logger isn't flushed, so the maintenance thread ends the run
+        for _ in 0..3 {
+            logger.log_blocking(LogLevel::Warning, "", ("", 0, 0), "connection lost");
+        }
+        std::thread::sleep(Duration::from_secs(1));
+
+        let log_file = fs::read_dir(&log_directory).unwrap().next().unwrap().unwrap().path();
+        let records = fs::read_to_string(log_file).unwrap();
+        drop(logger);
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(records, "connection lost\nconnection lost [repeated 2 times]\n");
+    }
+
+    #[test]
+    fn suppressed_records_are_summarized_without_another_record() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_summaries_{}", std::process::id()));
+        let config = inner_with_config(TheLoggerConfig::default().log_directory(&log_directory)).config;
+        let logger = TheLogger::new(config, None);
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            //  The call sites aren't hit again, so the summaries are written when the window ends and on flush
+            for _ in 0..3 {
+                crate::log_warning!(logger, every = 200ms; "noisy record");
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            for _ in 0..3 {
+                crate::log_warning!(logger, every = 1h; "noisy record");
+            }
+            logger.flush().await.unwrap();
+        });
+
+        let log_file = fs::read_dir(&log_directory).unwrap().next().unwrap().unwrap().path();
+        let records = fs::read_to_string(log_file).unwrap();
+        drop(logger);
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(records, "noisy record\nsuppressed 2 similar messages\n".repeat(2));
+    }
+
+    #[test]
+    fn failures_are_handled_by_the_configured_strategy() {
+        //  The logs directory can't be created inside a file until the file is removed
+        let blocking_file = std::env::temp_dir().join(format!("the_logger_failures_{}", std::process::id()));
+        fs::write(&blocking_file, "").unwrap();
+        //  Error and record of every failure reported to the callback
+        type Failures = std::sync::Arc<Mutex<Vec<(String, Option<String>)>>>;
+        let failures = Failures::default();
+        let callback_failures = failures.clone();
+        let callback = FailureStrategy::Callback(std::sync::Arc::new(move |error, record| {
+            let failure = (error.to_string(), record.map(str::to_string));
+            callback_failures.lock().unwrap().push(failure);
+        }));
+
+        for strategy in [FailureStrategy::Ignore, FailureStrategy::Stderr, callback] {
+            let config = TheLoggerConfig::default().log_directory(blocking_file.join("logs")).on_failure(strategy);
+            let mut inner = inner_with_config(config);
+            inner.log_record(LogLevel::Error, "unwritable record");
+            assert!(inner.failing);
+        }
+        let failures = failures.lock().unwrap().clone();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].0.starts_with("couldn't create the logs directory"));
+        assert_eq!(failures[0].1.as_deref(), Some("unwritable record"));
+
+        //  The logger recovers once the file can be opened
+        let log_directory = std::env::temp_dir().join(format!("the_logger_recovery_{}", std::process::id()));
+        let mut inner = inner_with_config(TheLoggerConfig::default().log_directory(&log_directory));
+        inner.failing = true;
+        inner.log_record(LogLevel::Information, "written record");
+        assert!(!inner.failing);
+        fs::remove_file(&blocking_file).unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+    }
+
+    #[test]
+    fn records_logged_from_a_callback_are_discarded() {
+        //  The logs directory can't be created inside a file, so every record fails and invokes the callback
+        let blocking_file = std::env::temp_dir().join(format!("the_logger_reentrant_{}", std::process::id()));
+        fs::write(&blocking_file, "").unwrap();
+        let logger_cell: std::sync::Arc<OnceLock<&'static TheLogger>> = Default::default();
+        let failures = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (callback_logger, callback_failures) = (logger_cell.clone(), failures.clone());
+        let config = TheLoggerConfig::default()
+            .log_directory(blocking_file.join("logs"))
+            .on_failure(FailureStrategy::Callback(std::sync::Arc::new(move |_, _| {
+                callback_failures.fetch_add(1, Ordering::SeqCst);
+                let logger = callback_logger.get().unwrap();
+                logger.log_blocking(LogLevel::Warning, "", ("", 0, 0), "from the callback");
+                assert!(poll_once(logger.log_in_file(("", 0, 0), "from the callback")).is_ready());
+                let fields = [("retry", FieldValue::from(true))];
+                let record = logger.log_in_file_with_fields(LogLevel::Warning, "", ("", 0, 0), &fields, "callback");
+                assert!(poll_once(record).is_ready());
+            })));
+        let logger: &'static TheLogger = Box::leak(Box::new(TheLogger::new(config, None)));
+        let _ = logger_cell.set(logger);
+
+        logger.log_blocking(LogLevel::Error, "", (file!(), line!(), column!()), "unwritable record");
+        logger.log_blocking(LogLevel::Error, "", (file!(), line!(), column!()), "unwritable record");
+        fs::remove_file(&blocking_file).unwrap();
+        assert_eq!(failures.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_logger_used_from_a_callback_is_left_unchanged() {
+        let blocking_file = std::env::temp_dir().join(format!("the_logger_reentrant_use_{}", std::process::id()));
+        fs::write(&blocking_file, "").unwrap();
+        let logger_cell: std::sync::Arc<OnceLock<&'static TheLogger>> = Default::default();
+        let callback_logger = logger_cell.clone();
+        let config = TheLoggerConfig::default()
+            .log_directory(blocking_file.join("logs"))
+            .on_failure(FailureStrategy::Callback(std::sync::Arc::new(move |_, _| {
+                let logger = callback_logger.get().unwrap();
+                assert!(poll_once(logger.verbose()).is_ready());
+                assert!(poll_once(logger.log_file_prefix("Callback")).is_ready());
+                assert!(poll_once(logger.config(TheLoggerConfig::default())).is_ready());
+                assert!(matches!(poll_once(logger.flush()), std::task::Poll::Ready(Err(TheLoggerError::Reentrant))));
+                assert!(matches!(poll_once(logger.shutdown()), std::task::Poll::Ready(Err(TheLoggerError::Reentrant))));
+                drop(logger.shutdown_guard());
+            })));
+        let logger: &'static TheLogger = Box::leak(Box::new(TheLogger::new(config, None)));
+        let _ = logger_cell.set(logger);
+
+        logger.log_blocking(LogLevel::Error, "", (file!(), line!(), column!()), "unwritable record");
+        fs::remove_file(&blocking_file).unwrap();
+        let inner = logger.lock().unwrap();
+        assert_eq!(inner.config.get_log_level(), TheLoggerConfig::default().get_log_level());
+        assert_eq!(inner.config.get_log_file_prefix(), "Log");
+        assert!(inner.config.get_log_directory().starts_with(&blocking_file));
+        assert!(!logger.shut_down.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "redaction")]
+    #[test]
+    fn records_logged_from_a_redaction_callback_are_discarded() {
+        let logger_cell: std::sync::Arc<OnceLock<&'static TheLogger>> = Default::default();
+        let callback_logger = logger_cell.clone();
+        let config = TheLoggerConfig::default().redact(RedactionRule::callback(move |text| {
+            let logger = callback_logger.get().unwrap();
+            assert!(poll_once(logger.log_in_file(("", 0, 0), "from the callback")).is_ready());
+            text.replace("hunter2", "***")
+        }));
+        let logger: &'static TheLogger = Box::leak(Box::new(TheLogger::new(config, None)));
+        let _ = logger_cell.set(logger);
+
+        let capture = TheLogger::capture();
+        logger.log_blocking(LogLevel::Information, "", ("", 0, 0), "password hunter2");
+        let records = capture.records();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].message.ends_with("password ***"));
+    }
+
+    #[test]
+    fn shutdown_drains_the_records_being_logged() {
+        const RECORDS: usize = 8;
+        let log_directory = std::env::temp_dir().join(format!("the_logger_drain_{}", std::process::id()));
+        let config = inner_with_config(TheLoggerConfig::default().log_directory(&log_directory)).config;
+        let logger: &'static TheLogger = Box::leak(Box::new(TheLogger::new(config, None)));
+
+        //  Hold the lock so every record is started before the shutdown, and waits for the lock until after it
+        let inner = logger.lock().unwrap();
+        let writers: Vec<_> = (0..RECORDS)
+            .map(|record| std::thread::spawn(move || {
+                logger.log_blocking(LogLevel::Information, "", ("", 0, 0), &format!("record {}", record));
+            }))
+            .collect();
+        while *logger.in_flight.lock().unwrap() < RECORDS {
+            std::thread::yield_now();
+        }
+        let shutdown = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(logger.shutdown()).unwrap();
+        });
+        while !logger.shut_down.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+        drop(inner);
+        writers.into_iter().chain([shutdown]).for_each(|thread| thread.join().unwrap());
+
+        let log_file = fs::read_dir(&log_directory).unwrap().next().unwrap().unwrap().path();
+        let records = fs::read_to_string(log_file).unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert_eq!(records.lines().count(), RECORDS);
+    }
+
+    #[test]
+    fn records_are_flushed_according_to_the_policy() {
+        let log_directory = std::env::temp_dir().join(format!("the_logger_flush_policy_{}", std::process::id()));
+        let logged_records = |prefix: &str| {
+            let log_file = log_directory.join(format!("{} {}.log", prefix, chrono::Local::now().format("%Y-%m-%d")));
+            fs::read_to_string(log_file).map(|records| records.lines().count()).unwrap_or(0)
+        };
+        let logger = |prefix: &str, policy: FlushPolicy| {
+            let config = TheLoggerConfig::default().log_directory(&log_directory).log_file_prefix(prefix)
+                .flush_policy(policy);
+            TheLogger::new(inner_with_config(config).config, None)
+        };
+
+        let every_records = logger("EveryRecords", FlushPolicy::EveryRecords(3));
+        for _ in 0..2 {
+            every_records.log_blocking(LogLevel::Information, "", ("", 0, 0), "buffered record");
+        }
+        assert_eq!(logged_records("EveryRecords"), 0);
+        every_records.log_blocking(LogLevel::Information, "", ("", 0, 0), "third record");
+        assert_eq!(logged_records("EveryRecords"), 3);
+
+        let on_error = logger("OnErrorOrCritical", FlushPolicy::OnErrorOrCritical);
+        on_error.log_blocking(LogLevel::Warning, "", ("", 0, 0), "buffered record");
+        assert_eq!(logged_records("OnErrorOrCritical"), 0);
+        on_error.log_blocking(LogLevel::Error, "", ("", 0, 0), "error record");
+        assert_eq!(logged_records("OnErrorOrCritical"), 2);
+
+        //  No other record is written, so the maintenance thread is the one flushing it
+        let every_millis = logger("EveryMillis", FlushPolicy::EveryMillis(200));
+        every_millis.log_blocking(LogLevel::Information, "", ("", 0, 0), "buffered record");
+        assert_eq!(logged_records("EveryMillis"), 0);
+        std::thread::sleep(Duration::from_secs(1));
+        assert_eq!(logged_records("EveryMillis"), 1);
+
+        drop((every_records, on_error, every_millis));
+        fs::remove_dir_all(&log_directory).unwrap();
+    }
+
+    #[cfg(feature = "audit")]
+    #[test]
+    fn the_chain_is_resumed_from_the_open_file() {
+        //  A file opened on a previous day is still the one the chain must be resumed from
+        let log_directory = std::env::temp_dir().join(format!("the_logger_open_chain_{}", std::process::id()));
+        fs::create_dir_all(&log_directory).unwrap();
+        let log_file = log_directory.join("Log 2000-01-01.log");
+        let mut inner = inner_with_config(TheLoggerConfig::default().log_directory(&log_directory).enable_hash_chain());
+        inner.file_writer = Some(BufWriter::new(File::options().create(true).append(true).open(&log_file).unwrap()));
+        inner.file_path = Some(log_file.clone());
+
+        inner.log_record(LogLevel::Information, "chained record");
+        inner.shutdown().unwrap();
+        let violations = crate::verify_hash_chain(&log_directory, "Log").unwrap();
+        let records = fs::read_to_string(&log_file).unwrap();
+        fs::remove_dir_all(&log_directory).unwrap();
+        assert!(records.contains("chained record #hash="));
+        assert_eq!(violations, []);
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn the_segment_is_started_on_the_open_file() {
+        //  A file opened on a previous day is the one that must be checked for incomplete frames
+        let log_directory = std::env::temp_dir().join(format!("the_logger_open_segment_{}", std::process::id()));
+        fs::create_dir_all(&log_directory).unwrap();
+        let log_file = log_directory.join("Log 2000-01-01.log.enc");
+        let key = EncryptionKey::new([3; 32]);
+        let config = TheLoggerConfig::default().log_directory(&log_directory).enable_encryption(key.clone());
+        let mut inner = inner_with_config(config);
+        fs::write(&log_file, [0x02, 0, 0]).unwrap();
+        inner.file_writer = Some(BufWriter::new(File::options().append(true).open(&log_file).unwrap()));
+        inner.file_path = Some(log_file.clone());
+
+        inner.log_record(LogLevel::Information, "encrypted record");
+        inner.shutdown().unwrap();
+        let records: Result<Vec<String>, TheLoggerError> =
+            crate::EncryptedLogReader::open(&log_file, &key).unwrap().collect();
+        fs::remove_dir_all(&log_directory).unwrap();
+        let records = records.unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].contains("encrypted record"));
+    }
+}
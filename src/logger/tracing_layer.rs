@@ -0,0 +1,218 @@
+use std::fmt::{Debug, Write};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use crate::logger::fields::FieldValue;
+use crate::logger::logger_config::LogLevel;
+use crate::logger::the_logger::TheLogger;
+
+/// Layer for tracing subscribers that writes every event through TheLogger's formatting and log file. Created with
+/// [`TheLogger::tracing_layer`].
+///
+/// Each record contains the names and fields of the spans the event happened in, from the outermost to the innermost,
+/// followed by the event's message and its fields:
+/// ```text
+/// 2023-12-17 01:51:10.677106  [INFO]  @src/main.rs: 37    request{id=7}:load_user{user_id=42}: user loaded cached=true
+/// ```
+pub struct TheLoggerLayer {
+    logger: &'static TheLogger
+}
+
+#[doc(hidden)]
+/// Formatted fields of a span, stored in its extensions when the span is created
+struct SpanFields(String);
+
+#[doc(hidden)]
+/// Visitor that formats the fields as k=v pairs, keeping the message field apart when there's one. The values are
+/// formatted as [`FieldValue`]s, so text values are quoted like the fields of the log macros
+struct FieldsVisitor<'a> {
+    message: Option<&'a mut String>,
+    fields: &'a mut String
+}
+
+impl FieldsVisitor<'_> {
+    #[doc(hidden)]
+    fn push_field(&mut self, field: &Field, value: FieldValue) {
+        match self.message.as_mut() {
+            Some(message) if field.name() == "message" => match value {
+                FieldValue::Str(value) => message.push_str(&value),
+                value => {
+                    let _ = write!(message, "{}", value);
+                }
+            },
+            _ => {
+                if !self.fields.is_empty() {
+                    self.fields.push(' ');
+                }
+                let _ = write!(self.fields, "{}={}", field.name(), value);
+            }
+        }
+    }
+}
+
+impl Visit for FieldsVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push_field(field, FieldValue::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push_field(field, FieldValue::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push_field(field, FieldValue::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push_field(field, FieldValue::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push_field(field, FieldValue::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.push_field(field, FieldValue::debug(value));
+    }
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warning,
+            Level::INFO => LogLevel::Information,
+            Level::DEBUG => LogLevel::Debug,
+            Level::TRACE => LogLevel::Trace
+        }
+    }
+}
+
+impl<S> Layer<S> for TheLoggerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = String::new();
+            attrs.record(&mut FieldsVisitor { message: None, fields: &mut fields });
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(&mut FieldsVisitor { message: None, fields });
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if self.logger.is_shut_down() {
+            return;
+        }
+
+        //  Span path, from the outermost span to the innermost one
+        let mut msg = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if !msg.is_empty() {
+                    msg.push(':');
+                }
+                msg.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    if !fields.is_empty() {
+                        let _ = write!(msg, "{{{}}}", fields);
+                    }
+                }
+            }
+            if !msg.is_empty() {
+                msg.push_str(": ");
+            }
+        }
+
+        //  Event message and fields
+        let mut message = String::new();
+        let mut fields = String::new();
+        event.record(&mut FieldsVisitor { message: Some(&mut message), fields: &mut fields });
+        msg.push_str(&message);
+        if !fields.is_empty() {
+            if !message.is_empty() {
+                msg.push(' ');
+            }
+            msg.push_str(&fields);
+        }
+
+        let metadata = event.metadata();
+        let file = metadata.file()
+            .or(metadata.module_path())
+            .unwrap_or(metadata.target());
+        self.logger.log_blocking(
+            metadata.level().into(),
+            metadata.module_path().unwrap_or_default(),
+            (file, metadata.line().unwrap_or(0), 0),
+            &msg
+        );
+    }
+}
+
+impl TheLogger {
+    /// ## Description
+    /// Returns a layer for tracing subscribers that writes every event into this logger's file, including the names
+    /// and fields of the spans it happened in. Tracing levels are mapped as follows: ERROR to Error, WARN to Warning,
+    /// INFO to Information, DEBUG to Debug and TRACE to Trace.
+    ///
+    /// ### Usage example
+    /// ```rust
+    /// use the_logger::TheLogger;
+    /// use tracing_subscriber::layer::SubscriberExt;
+    ///
+    /// fn init_tracing() {
+    ///     let subscriber = tracing_subscriber::registry().with(TheLogger::instance().tracing_layer());
+    ///     tracing::subscriber::set_global_default(subscriber).expect("Couldn't set the tracing subscriber");
+    ///
+    ///     let span = tracing::info_span!("load_user", user_id = 42);
+    ///     let _entered = span.enter();
+    ///     tracing::info!(cached = true, "user loaded");
+    /// }
+    /// ```
+    pub fn tracing_layer(&'static self) -> TheLoggerLayer {
+        TheLoggerLayer { logger: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+    use super::*;
+
+    #[test]
+    fn events_are_logged_with_their_span_path_and_fields() {
+        let capture = TheLogger::capture();
+        let subscriber = tracing_subscriber::registry().with(TheLogger::instance().tracing_layer());
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!("request", id = 7);
+            let _request = request.enter();
+            let load_user = tracing::debug_span!("load_user", user_id = 42, found = tracing::field::Empty);
+            let _load_user = load_user.enter();
+            load_user.record("found", true);
+            tracing::info!(cached = true, source = "redis", query = "id = 42", comment = "", "user loaded");
+            drop(_load_user);
+            tracing::warn!(retries = 3, tags = ?["slow"]);
+        });
+
+        let records = capture.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, LogLevel::Information);
+        assert!(records[0].message.ends_with(concat!(
+            "request{id=7}:load_user{user_id=42 found=true}: user loaded ",
+            r#"cached=true source=redis query="id = 42" comment="""#
+        )));
+        assert_eq!(records[1].level, LogLevel::Warning);
+        assert!(records[1].message.ends_with(r#"request{id=7}: retries=3 tags="[\"slow\"]""#));
+    }
+}